@@ -0,0 +1,1268 @@
+//! The traits that the FromBinary, ToBinary, and BinarySize derives
+//! from abes_nice_procs implement, along with implementations
+//! for the types you would expect to find in a struct.
+//!
+//! Everything is written little endian (unless it goes through a big endian
+//! [BinaryReader] or [BinaryWriter]) and anything with a
+//! length (like [Vec] and [String]) writes that length as a
+//! [u64] before the contents.
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::hash::{BuildHasher, Hash};
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Something that can be read from binary.
+/// Reading will panic if the data runs out or is invalid.
+pub trait FromBinary {
+    /// If having bytes left over after reading it is an error,
+    /// which from_binary_slice and [BinaryReader::read_binary] check.
+    /// The derive sets it with `#[binary(strict)]`
+    const STRICT: bool = false;
+    fn from_binary(binary: &mut dyn Read) -> Self;
+    /// Reads from the start of the bytes, ignoring any left over
+    /// unless it is [FromBinary::STRICT]
+    fn from_binary_slice(mut bytes: &[u8]) -> Self
+    where
+        Self: Sized
+    {
+        let out = Self::from_binary(&mut bytes);
+        if Self::STRICT && !bytes.is_empty() {
+            left_over::<Self>(bytes.len() as u64)
+        }
+        out
+    }
+    /// Reads over what is already there, which for things like [Vec] and [String]
+    /// keeps the memory they already have instead of making more
+    #[allow(clippy::wrong_self_convention)]// It goes with from_binary
+    fn from_binary_into(&mut self, binary: &mut dyn Read)
+    where
+        Self: Sized
+    {
+        *self = Self::from_binary(binary)
+    }
+}
+/// Something that can be read by borrowing from the bytes it is in
+/// instead of copying out of them, like `&[u8]` and `&str`.
+/// Everything that is [FromBinary] is too, by reading it normally.
+/// With `#[binary(zero_copy)]`, the FromBinary derive implements this instead,
+/// so the fields can borrow.
+pub trait FromBinaryBorrowed<'a>: Sized {
+    /// Reads from the start of the bytes, moving them to after what was read
+    fn from_binary_borrowed(binary: &mut &'a [u8]) -> Self;
+}
+impl<'a, T: FromBinary> FromBinaryBorrowed<'a> for T {
+    fn from_binary_borrowed(binary: &mut &'a [u8]) -> Self {
+        T::from_binary(binary)
+    }
+}
+/// Read the same as a [Vec] of [u8]
+impl<'a> FromBinaryBorrowed<'a> for &'a [u8] {
+    fn from_binary_borrowed(binary: &mut &'a [u8]) -> Self {
+        let len = usize::from_binary(binary);
+        if len > binary.len() {
+            panic!("ran out of data reading &[u8]: needed {len} bytes, but there are {}", binary.len())
+        }
+        let (out, rest) = binary.split_at(len);
+        *binary = rest;
+        out
+    }
+}
+/// Read the same as a [String]
+impl<'a> FromBinaryBorrowed<'a> for &'a str {
+    fn from_binary_borrowed(binary: &mut &'a [u8]) -> Self {
+        std::str::from_utf8(<&[u8]>::from_binary_borrowed(binary)).expect("invalid utf8 in &str")
+    }
+}
+/// Something that can be written as binary.
+/// Writing will panic if the writer fails.
+pub trait ToBinary {
+    fn to_binary(&self, write: &mut dyn Write);
+    /// Writes into a new [Vec]
+    fn to_binary_vec(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.to_binary(&mut out);
+        out
+    }
+}
+/// Something that can list which of its fields are written differently from another,
+/// usually made with the BinaryDiff derive.
+/// The fields are named like `name`, or `Variant.0` for enums,
+/// and being different variants gives `variant`.
+pub trait BinaryDiff {
+    fn binary_diff(&self, other: &Self) -> Vec<String>;
+}
+/// Something that knows how many bytes
+/// [ToBinary] writes for it, without writing it.
+pub trait BinarySize {
+    fn binary_size(&self) -> usize;
+}
+/// Something that [ToBinary] always writes the same number of bytes for,
+/// so that it can be known without having one,
+/// like for the size of a buffer on the stack.
+/// The FixedSize derive adds up the sizes of the fields.
+pub trait FixedSize {
+    const SERIALIZED_SIZE: usize;
+}
+
+macro_rules! number {
+    ($($type:ty),*) => {
+        $(
+            impl FromBinary for $type {
+                fn from_binary(binary: &mut dyn Read) -> Self {
+                    let mut buf = [0; std::mem::size_of::<$type>()];
+                    binary.read_exact(&mut buf).expect(concat!("failed to read ", stringify!($type)));
+                    match Endian::current() {
+                        Endian::Little => <$type>::from_le_bytes(buf),
+                        Endian::Big => <$type>::from_be_bytes(buf)
+                    }
+                }
+            }
+            impl ToBinary for $type {
+                fn to_binary(&self, write: &mut dyn Write) {
+                    let bytes = match Endian::current() {
+                        Endian::Little => self.to_le_bytes(),
+                        Endian::Big => self.to_be_bytes()
+                    };
+                    write.write_all(&bytes).expect(concat!("failed to write ", stringify!($type)))
+                }
+            }
+            impl BinarySize for $type {
+                fn binary_size(&self) -> usize {
+                    std::mem::size_of::<$type>()
+                }
+            }
+            impl FixedSize for $type {
+                const SERIALIZED_SIZE: usize = std::mem::size_of::<$type>();
+            }
+        )*
+    };
+}
+number!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+// Floats are written as their bits, so every value comes back bit for bit,
+// including negative zero and the payload of a NaN
+macro_rules! float {
+    ($($type:ty: $bits:ty),*) => {
+        $(
+            impl FromBinary for $type {
+                fn from_binary(binary: &mut dyn Read) -> Self {
+                    <$type>::from_bits(<$bits>::from_binary(binary))
+                }
+            }
+            impl ToBinary for $type {
+                fn to_binary(&self, write: &mut dyn Write) {
+                    self.to_bits().to_binary(write)
+                }
+            }
+            impl BinarySize for $type {
+                fn binary_size(&self) -> usize {
+                    std::mem::size_of::<$bits>()
+                }
+            }
+            impl FixedSize for $type {
+                const SERIALIZED_SIZE: usize = <$bits as FixedSize>::SERIALIZED_SIZE;
+            }
+        )*
+    };
+}
+float!(f32: u32, f64: u64);
+
+// Written the same as the number inside, which can't be zero when reading
+macro_rules! non_zero {
+    ($($type:ident($inner:ty)),*) => {
+        $(
+            impl FromBinary for std::num::$type {
+                fn from_binary(binary: &mut dyn Read) -> Self {
+                    std::num::$type::new(FromBinary::from_binary(binary))
+                        .expect(concat!("zero can't be read as a ", stringify!($type)))
+                }
+            }
+            impl ToBinary for std::num::$type {
+                fn to_binary(&self, write: &mut dyn Write) {
+                    self.get().to_binary(write)
+                }
+            }
+            impl BinarySize for std::num::$type {
+                fn binary_size(&self) -> usize {
+                    self.get().binary_size()
+                }
+            }
+            impl FixedSize for std::num::$type {
+                const SERIALIZED_SIZE: usize = <$inner as FixedSize>::SERIALIZED_SIZE;
+            }
+        )*
+    };
+}
+non_zero!(
+    NonZeroU8(u8), NonZeroU16(u16), NonZeroU32(u32), NonZeroU64(u64), NonZeroU128(u128), NonZeroUsize(usize),
+    NonZeroI8(i8), NonZeroI16(i16), NonZeroI32(i32), NonZeroI64(i64), NonZeroI128(i128), NonZeroIsize(isize)
+);
+
+// usize and isize are always written as 64 bits so that
+// the data is the same on every platform
+impl FromBinary for usize {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        u64::from_binary(binary).try_into().expect("usize does not fit on this platform")
+    }
+}
+impl ToBinary for usize {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (*self as u64).to_binary(write)
+    }
+}
+impl BinarySize for usize {
+    fn binary_size(&self) -> usize {
+        8
+    }
+}
+impl FixedSize for usize {
+    const SERIALIZED_SIZE: usize = 8;
+}
+impl FromBinary for isize {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        i64::from_binary(binary).try_into().expect("isize does not fit on this platform")
+    }
+}
+impl ToBinary for isize {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (*self as i64).to_binary(write)
+    }
+}
+impl BinarySize for isize {
+    fn binary_size(&self) -> usize {
+        8
+    }
+}
+impl FixedSize for isize {
+    const SERIALIZED_SIZE: usize = 8;
+}
+
+impl FromBinary for bool {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        match u8::from_binary(binary) {
+            0 => false,
+            1 => true,
+            other => panic!("invalid bool: {other}")
+        }
+    }
+}
+impl ToBinary for bool {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (*self as u8).to_binary(write)
+    }
+}
+impl BinarySize for bool {
+    fn binary_size(&self) -> usize {
+        1
+    }
+}
+impl FixedSize for bool {
+    const SERIALIZED_SIZE: usize = 1;
+}
+
+impl FromBinary for char {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let value = u32::from_binary(binary);
+        char::from_u32(value).unwrap_or_else(|| panic!("invalid char: {value:#x}"))
+    }
+}
+impl ToBinary for char {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (*self as u32).to_binary(write)
+    }
+}
+impl BinarySize for char {
+    fn binary_size(&self) -> usize {
+        4
+    }
+}
+impl FixedSize for char {
+    const SERIALIZED_SIZE: usize = 4;
+}
+
+// Nothing is written for (), so it is free to have in generic code
+impl FromBinary for () {
+    fn from_binary(_binary: &mut dyn Read) -> Self {}
+}
+impl ToBinary for () {
+    fn to_binary(&self, _write: &mut dyn Write) {}
+}
+impl BinarySize for () {
+    fn binary_size(&self) -> usize {
+        0
+    }
+}
+impl FixedSize for () {
+    const SERIALIZED_SIZE: usize = 0;
+}
+
+/// Written as the [u64] seconds and then the [u32] nanoseconds
+impl FromBinary for Duration {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let secs = u64::from_binary(binary);
+        let nanos = u32::from_binary(binary);
+        if nanos >= 1_000_000_000 {
+            panic!("invalid nanoseconds in Duration: {nanos}")
+        }
+        Duration::new(secs, nanos)
+    }
+}
+impl ToBinary for Duration {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.as_secs().to_binary(write);
+        self.subsec_nanos().to_binary(write);
+    }
+}
+impl BinarySize for Duration {
+    fn binary_size(&self) -> usize {
+        12
+    }
+}
+impl FixedSize for Duration {
+    const SERIALIZED_SIZE: usize = 12;
+}
+/// Written as the [Duration] since the [UNIX_EPOCH],
+/// so times before it can't be written
+impl FromBinary for SystemTime {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        UNIX_EPOCH.checked_add(Duration::from_binary(binary))
+            .expect("SystemTime is too far after the UNIX_EPOCH")
+    }
+}
+impl ToBinary for SystemTime {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.duration_since(UNIX_EPOCH)
+            .expect("SystemTime is before the UNIX_EPOCH, which can't be written")
+            .to_binary(write)
+    }
+}
+impl BinarySize for SystemTime {
+    fn binary_size(&self) -> usize {
+        12
+    }
+}
+impl FixedSize for SystemTime {
+    const SERIALIZED_SIZE: usize = 12;
+}
+
+/// Reads the octets of an address, which are always in the same order
+/// since that is the order they are written in anyway
+fn octets<const N: usize>(binary: &mut dyn Read) -> [u8; N] {
+    let mut octets = [0; N];
+    binary.read_exact(&mut octets).expect("failed to read address");
+    octets
+}
+/// Written as its 4 octets
+impl FromBinary for Ipv4Addr {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        Ipv4Addr::from(octets::<4>(binary))
+    }
+}
+impl ToBinary for Ipv4Addr {
+    fn to_binary(&self, write: &mut dyn Write) {
+        write.write_all(&self.octets()).expect("failed to write")
+    }
+}
+impl BinarySize for Ipv4Addr {
+    fn binary_size(&self) -> usize {
+        4
+    }
+}
+impl FixedSize for Ipv4Addr {
+    const SERIALIZED_SIZE: usize = 4;
+}
+/// Written as its 16 octets
+impl FromBinary for Ipv6Addr {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        Ipv6Addr::from(octets::<16>(binary))
+    }
+}
+impl ToBinary for Ipv6Addr {
+    fn to_binary(&self, write: &mut dyn Write) {
+        write.write_all(&self.octets()).expect("failed to write")
+    }
+}
+impl BinarySize for Ipv6Addr {
+    fn binary_size(&self) -> usize {
+        16
+    }
+}
+impl FixedSize for Ipv6Addr {
+    const SERIALIZED_SIZE: usize = 16;
+}
+/// Written as a [u8] of 4 or 6 for which version it is, then the address
+impl FromBinary for IpAddr {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        match u8::from_binary(binary) {
+            4 => IpAddr::V4(Ipv4Addr::from_binary(binary)),
+            6 => IpAddr::V6(Ipv6Addr::from_binary(binary)),
+            other => panic!("invalid IpAddr version: {other}")
+        }
+    }
+}
+impl ToBinary for IpAddr {
+    fn to_binary(&self, write: &mut dyn Write) {
+        match self {
+            IpAddr::V4(address) => {
+                4_u8.to_binary(write);
+                address.to_binary(write);
+            }
+            IpAddr::V6(address) => {
+                6_u8.to_binary(write);
+                address.to_binary(write);
+            }
+        }
+    }
+}
+impl BinarySize for IpAddr {
+    fn binary_size(&self) -> usize {
+        match self {
+            IpAddr::V4(_) => 5,
+            IpAddr::V6(_) => 17
+        }
+    }
+}
+/// Written as the [IpAddr] and then the [u16] port.
+/// The flow info and scope id of a v6 one aren't kept.
+impl FromBinary for SocketAddr {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let ip = IpAddr::from_binary(binary);
+        SocketAddr::new(ip, u16::from_binary(binary))
+    }
+}
+impl ToBinary for SocketAddr {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.ip().to_binary(write);
+        self.port().to_binary(write);
+    }
+}
+impl BinarySize for SocketAddr {
+    fn binary_size(&self) -> usize {
+        self.ip().binary_size() + 2
+    }
+}
+
+impl FromBinary for String {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        String::from_utf8(Vec::<u8>::from_binary(binary)).expect("invalid utf8 in String")
+    }
+    fn from_binary_into(&mut self, binary: &mut dyn Read) {
+        let mut bytes = std::mem::take(self).into_bytes();
+        bytes.from_binary_into(binary);
+        *self = String::from_utf8(bytes).expect("invalid utf8 in String")
+    }
+}
+impl ToBinary for String {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.as_str().to_binary(write)
+    }
+}
+/// Written the same as a [String]
+impl ToBinary for str {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.len().to_binary(write);
+        write.write_all(self.as_bytes()).expect("failed to write str")
+    }
+}
+impl BinarySize for str {
+    fn binary_size(&self) -> usize {
+        8 + self.len()
+    }
+}
+impl BinarySize for String {
+    fn binary_size(&self) -> usize {
+        8 + self.len()
+    }
+}
+
+// On unix, these are written as the bytes they already are, so any one comes back the same.
+// Anywhere else, they are written as UTF-8, which turns anything that isn't into �.
+// Either way it is written the same as a String when it is UTF-8,
+// so paths that are can go between platforms.
+fn os_bytes(os: &OsStr) -> Cow<'_, [u8]> {
+    #[cfg(unix)]
+    return Cow::Borrowed(std::os::unix::ffi::OsStrExt::as_bytes(os));
+    #[cfg(not(unix))]
+    return match os.to_string_lossy() {
+        Cow::Borrowed(str) => Cow::Borrowed(str.as_bytes()),
+        Cow::Owned(string) => Cow::Owned(string.into_bytes())
+    };
+}
+impl FromBinary for OsString {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        #[cfg(unix)]
+        return std::os::unix::ffi::OsStringExt::from_vec(Vec::<u8>::from_binary(binary));
+        #[cfg(not(unix))]
+        return String::from_binary(binary).into();
+    }
+}
+impl ToBinary for OsString {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.as_os_str().to_binary(write)
+    }
+}
+/// Written the same as an [OsString]
+impl ToBinary for OsStr {
+    fn to_binary(&self, write: &mut dyn Write) {
+        let bytes = os_bytes(self);
+        bytes.len().to_binary(write);
+        write.write_all(&bytes).expect("failed to write OsStr")
+    }
+}
+impl BinarySize for OsStr {
+    fn binary_size(&self) -> usize {
+        8 + os_bytes(self).len()
+    }
+}
+impl BinarySize for OsString {
+    fn binary_size(&self) -> usize {
+        self.as_os_str().binary_size()
+    }
+}
+/// Written the same as an [OsString]
+impl FromBinary for PathBuf {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        OsString::from_binary(binary).into()
+    }
+}
+impl ToBinary for PathBuf {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.as_os_str().to_binary(write)
+    }
+}
+impl ToBinary for Path {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.as_os_str().to_binary(write)
+    }
+}
+impl BinarySize for Path {
+    fn binary_size(&self) -> usize {
+        self.as_os_str().binary_size()
+    }
+}
+impl BinarySize for PathBuf {
+    fn binary_size(&self) -> usize {
+        self.as_os_str().binary_size()
+    }
+}
+
+/// How many of `T` to make room for up front when the length says there are len.
+/// The length could be anything, so only up to about 4KB gets allocated before
+/// any of them are read, and the rest grows as they are, which means a bad length
+/// runs out of data (or hits a limit) instead of asking for too much memory
+fn preallocate<T>(len: usize) -> usize {
+    len.min(4096 / std::mem::size_of::<T>().max(1))
+}
+impl<T: FromBinary> FromBinary for Vec<T> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let len = usize::from_binary(binary);
+        let mut out = Vec::with_capacity(preallocate::<T>(len));
+        for _ in 0..len {
+            out.push(T::from_binary(binary));
+        }
+        out
+    }
+    fn from_binary_into(&mut self, binary: &mut dyn Read) {
+        let len = usize::from_binary(binary);
+        self.truncate(len);
+        // Reading over the items that are already there
+        // lets them keep their memory too
+        for item in self.iter_mut() {
+            item.from_binary_into(binary);
+        }
+        for _ in self.len()..len {
+            self.push(T::from_binary(binary));
+        }
+    }
+}
+impl<T: ToBinary> ToBinary for Vec<T> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.as_slice().to_binary(write)
+    }
+}
+/// Written the same as a [Vec]
+impl<T: ToBinary> ToBinary for [T] {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.len().to_binary(write);
+        for item in self {
+            item.to_binary(write);
+        }
+    }
+}
+impl<T: BinarySize> BinarySize for [T] {
+    fn binary_size(&self) -> usize {
+        8 + self.iter().map(T::binary_size).sum::<usize>()
+    }
+}
+
+// There is nothing to read a reference into,
+// so these can only be written
+impl<T: ToBinary + ?Sized> ToBinary for &T {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (**self).to_binary(write)
+    }
+}
+impl<T: BinarySize + ?Sized> BinarySize for &T {
+    fn binary_size(&self) -> usize {
+        (**self).binary_size()
+    }
+}
+impl<T: BinarySize> BinarySize for Vec<T> {
+    fn binary_size(&self) -> usize {
+        8 + self.iter().map(T::binary_size).sum::<usize>()
+    }
+}
+
+impl<T: FromBinary> FromBinary for Option<T> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        match bool::from_binary(binary) {
+            true => Some(T::from_binary(binary)),
+            false => None
+        }
+    }
+}
+impl<T: ToBinary> ToBinary for Option<T> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        match self {
+            Some(item) => {
+                true.to_binary(write);
+                item.to_binary(write);
+            }
+            None => false.to_binary(write)
+        }
+    }
+}
+impl<T: BinarySize> BinarySize for Option<T> {
+    fn binary_size(&self) -> usize {
+        1 + self.as_ref().map_or(0, T::binary_size)
+    }
+}
+/// Written as each item one after the other, without a length
+/// since it is always the same
+impl<T: FromBinary, const N: usize> FromBinary for [T; N] {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        // from_fn goes through them in order
+        std::array::from_fn(|_| T::from_binary(binary))
+    }
+    fn from_binary_into(&mut self, binary: &mut dyn Read) {
+        for item in self {
+            item.from_binary_into(binary);
+        }
+    }
+}
+impl<T: ToBinary, const N: usize> ToBinary for [T; N] {
+    fn to_binary(&self, write: &mut dyn Write) {
+        for item in self {
+            item.to_binary(write);
+        }
+    }
+}
+impl<T: BinarySize, const N: usize> BinarySize for [T; N] {
+    fn binary_size(&self) -> usize {
+        self.iter().map(T::binary_size).sum()
+    }
+}
+impl<T: FixedSize, const N: usize> FixedSize for [T; N] {
+    const SERIALIZED_SIZE: usize = T::SERIALIZED_SIZE * N;
+}
+/// Written as a [u8] of 0 for [Ok] or 1 for [Err], then what is in it.
+/// This is only for storing one as data,
+/// reading an [Err] is still a successful read.
+impl<T: FromBinary, E: FromBinary> FromBinary for Result<T, E> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        match u8::from_binary(binary) {
+            0 => Ok(T::from_binary(binary)),
+            1 => Err(E::from_binary(binary)),
+            other => panic!("invalid Result tag: {other}")
+        }
+    }
+}
+impl<T: ToBinary, E: ToBinary> ToBinary for Result<T, E> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        match self {
+            Ok(value) => {
+                0_u8.to_binary(write);
+                value.to_binary(write);
+            }
+            Err(error) => {
+                1_u8.to_binary(write);
+                error.to_binary(write);
+            }
+        }
+    }
+}
+impl<T: BinarySize, E: BinarySize> BinarySize for Result<T, E> {
+    fn binary_size(&self) -> usize {
+        1 + match self {
+            Ok(value) => value.binary_size(),
+            Err(error) => error.binary_size()
+        }
+    }
+}
+
+/// The map is made with a new `S`, so any hasher that has a [Default] can be read into.
+impl<K: FromBinary + Eq + Hash, V: FromBinary, S: BuildHasher + Default> FromBinary for HashMap<K, V, S> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let len = usize::from_binary(binary);
+        let mut out = HashMap::with_capacity_and_hasher(preallocate::<(K, V)>(len), S::default());
+        for _ in 0..len {
+            let key = K::from_binary(binary);
+            out.insert(key, V::from_binary(binary));
+        }
+        out
+    }
+}
+/// The entries are written in whatever order the map iterates,
+/// which isn't stable, so the same map can be written differently.
+/// If the bytes need to always be the same, use a [BTreeMap].
+impl<K: ToBinary, V: ToBinary, S> ToBinary for HashMap<K, V, S> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.len().to_binary(write);
+        for (key, value) in self {
+            key.to_binary(write);
+            value.to_binary(write);
+        }
+    }
+}
+impl<K: BinarySize, V: BinarySize, S> BinarySize for HashMap<K, V, S> {
+    fn binary_size(&self) -> usize {
+        8 + self.iter().map(|(key, value)| key.binary_size() + value.binary_size()).sum::<usize>()
+    }
+}
+impl<K: FromBinary + Ord, V: FromBinary> FromBinary for BTreeMap<K, V> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let len = usize::from_binary(binary);
+        let mut out = BTreeMap::new();
+        for _ in 0..len {
+            let key = K::from_binary(binary);
+            out.insert(key, V::from_binary(binary));
+        }
+        out
+    }
+}
+impl<K: ToBinary, V: ToBinary> ToBinary for BTreeMap<K, V> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        self.len().to_binary(write);
+        for (key, value) in self {
+            key.to_binary(write);
+            value.to_binary(write);
+        }
+    }
+}
+impl<K: BinarySize, V: BinarySize> BinarySize for BTreeMap<K, V> {
+    fn binary_size(&self) -> usize {
+        8 + self.iter().map(|(key, value)| key.binary_size() + value.binary_size()).sum::<usize>()
+    }
+}
+
+/// A [u64] written in as few bytes as it fits in(LEB128).
+/// Each byte has 7 bits of the number, starting with the lowest,
+/// and the top bit says whether there is another byte after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct VarInt(pub u64);
+impl FromBinary for VarInt {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let mut out = 0;
+        let mut shift = 0;
+        loop {
+            let byte = u8::from_binary(binary);
+            if shift >= 64 {
+                panic!("varint is too long")
+            }
+            // Only the lowest bit of the last byte is still in a u64
+            if shift == 63 && byte & 0x7e != 0 {
+                panic!("varint is too big for a u64")
+            }
+            out |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return VarInt(out)
+            }
+            shift += 7;
+        }
+    }
+}
+impl ToBinary for VarInt {
+    fn to_binary(&self, write: &mut dyn Write) {
+        let mut value = self.0;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                return byte.to_binary(write)
+            }
+            (byte | 0x80).to_binary(write);
+        }
+    }
+}
+impl BinarySize for VarInt {
+    fn binary_size(&self) -> usize {
+        let bits = (64 - self.0.leading_zeros() as usize).max(1);
+        bits.div_ceil(7)
+    }
+}
+
+/// A string written with a 0 after it instead of its length before it, like in C,
+/// which is what `#[binary(cstr)]` on a field writes it as.
+/// Writing one with a 0 in it panics, since reading it would stop there.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct NulTerminated<S>(pub S);
+impl FromBinary for NulTerminated<String> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        let mut bytes = Vec::new();
+        let mut byte = [0];
+        loop {
+            binary.read_exact(&mut byte).expect("ran out of data before the end of a NulTerminated");
+            if byte[0] == 0 {
+                return NulTerminated(String::from_utf8(bytes).expect("invalid utf8 in NulTerminated"))
+            }
+            bytes.push(byte[0]);
+        }
+    }
+}
+impl<'a> FromBinaryBorrowed<'a> for NulTerminated<&'a str> {
+    fn from_binary_borrowed(binary: &mut &'a [u8]) -> Self {
+        let len = binary.iter()
+            .position(|byte| *byte == 0)
+            .expect("ran out of data before the end of a NulTerminated");
+        let (out, rest) = binary.split_at(len);
+        *binary = &rest[1..];
+        NulTerminated(std::str::from_utf8(out).expect("invalid utf8 in NulTerminated"))
+    }
+}
+impl<S: AsRef<str>> ToBinary for NulTerminated<S> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        let bytes = self.0.as_ref().as_bytes();
+        if bytes.contains(&0) {
+            panic!("{:?} has a nul in it, so it can't be written as a NulTerminated", self.0.as_ref())
+        }
+        write.write_all(bytes).expect("failed to write NulTerminated");
+        write.write_all(&[0]).expect("failed to write NulTerminated")
+    }
+}
+impl<S: AsRef<str>> BinarySize for NulTerminated<S> {
+    fn binary_size(&self) -> usize {
+        self.0.as_ref().len() + 1
+    }
+}
+// For going to and from it with `#[binary(cstr)]`, the same as with `as = Type`
+impl From<String> for NulTerminated<String> {
+    fn from(value: String) -> Self {
+        NulTerminated(value)
+    }
+}
+impl<'a> From<&'a str> for NulTerminated<&'a str> {
+    fn from(value: &'a str) -> Self {
+        NulTerminated(value)
+    }
+}
+impl From<NulTerminated<String>> for String {
+    fn from(value: NulTerminated<String>) -> Self {
+        value.0
+    }
+}
+impl<'a> From<NulTerminated<&'a str>> for &'a str {
+    fn from(value: NulTerminated<&'a str>) -> Self {
+        value.0
+    }
+}
+
+impl<T: FromBinary> FromBinary for Box<T> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        Box::new(T::from_binary(binary))
+    }
+}
+impl<T: ToBinary> ToBinary for Box<T> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (**self).to_binary(write)
+    }
+}
+impl<T: BinarySize> BinarySize for Box<T> {
+    fn binary_size(&self) -> usize {
+        (**self).binary_size()
+    }
+}
+impl<T: FixedSize> FixedSize for Box<T> {
+    const SERIALIZED_SIZE: usize = T::SERIALIZED_SIZE;
+}
+/// Every one read is a new [Rc], so sharing isn't kept.
+impl<T: FromBinary> FromBinary for Rc<T> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        Rc::new(T::from_binary(binary))
+    }
+}
+/// Every [Rc] is written in full even if it is shared.
+impl<T: ToBinary> ToBinary for Rc<T> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (**self).to_binary(write)
+    }
+}
+impl<T: BinarySize> BinarySize for Rc<T> {
+    fn binary_size(&self) -> usize {
+        (**self).binary_size()
+    }
+}
+impl<T: FixedSize> FixedSize for Rc<T> {
+    const SERIALIZED_SIZE: usize = T::SERIALIZED_SIZE;
+}
+/// The same as for [Rc].
+impl<T: FromBinary> FromBinary for Arc<T> {
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        Arc::new(T::from_binary(binary))
+    }
+}
+/// The same as for [Rc].
+impl<T: ToBinary> ToBinary for Arc<T> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (**self).to_binary(write)
+    }
+}
+impl<T: BinarySize> BinarySize for Arc<T> {
+    fn binary_size(&self) -> usize {
+        (**self).binary_size()
+    }
+}
+impl<T: FixedSize> FixedSize for Arc<T> {
+    const SERIALIZED_SIZE: usize = T::SERIALIZED_SIZE;
+}
+
+/// Always read as [Cow::Owned], since there is nothing to borrow from
+impl<B: ToOwned + ?Sized> FromBinary for Cow<'_, B>
+where
+    B::Owned: FromBinary
+{
+    fn from_binary(binary: &mut dyn Read) -> Self {
+        Cow::Owned(B::Owned::from_binary(binary))
+    }
+}
+/// Written the same as what is in it, whether it is borrowed or owned
+impl<B: ToBinary + ToOwned + ?Sized> ToBinary for Cow<'_, B> {
+    fn to_binary(&self, write: &mut dyn Write) {
+        (**self).to_binary(write)
+    }
+}
+impl<B: BinarySize + ToOwned + ?Sized> BinarySize for Cow<'_, B> {
+    fn binary_size(&self) -> usize {
+        (**self).binary_size()
+    }
+}
+
+/// The order the bytes of numbers are in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big
+}
+thread_local! {
+    static ENDIAN: Cell<Endian> = const { Cell::new(Endian::Little) };
+}
+impl Endian {
+    /// The order numbers are being read and written in right now,
+    /// which is little unless it is inside of a big endian
+    /// [BinaryReader::read_binary] or [BinaryWriter::write_binary]
+    pub fn current() -> Endian {
+        ENDIAN.with(Cell::get)
+    }
+    /// Does `f` with numbers in this order, putting back the old one after even if it panics
+    fn scope<T>(self, f: impl FnOnce() -> T) -> T {
+        struct Restore(Endian);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                ENDIAN.with(|endian| endian.set(self.0))
+            }
+        }
+        let _restore = Restore(ENDIAN.with(|endian| endian.replace(self)));
+        f()
+    }
+}
+/// The error given when a [BinaryReader] or a [BinaryWriter] goes past its limit
+fn over_limit(limit: u64) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("went past the limit of {limit} bytes")
+    )
+}
+/// Wraps a reader to keep track of how much has been read,
+/// and optionally to stop more than a limit from being read,
+/// so that bad data can't make something read forever.
+/// It can be given to anything that takes `&mut dyn Read`,
+/// including the derived [FromBinary] impls,
+/// which panic if they go past the limit.
+///
+/// It can also read numbers as big endian, but only through [BinaryReader::read_binary],
+/// since that is the only place it knows what is being read.
+pub struct BinaryReader<R> {
+    inner: R,
+    position: u64,
+    limit: Option<u64>,
+    endian: Endian
+}
+impl<R: Read> BinaryReader<R> {
+    pub fn new(inner: R) -> Self {
+        BinaryReader {
+            inner,
+            position: 0,
+            limit: None,
+            endian: Endian::Little
+        }
+    }
+    /// Reads numbers as big endian instead of little
+    pub fn big_endian(inner: R) -> Self {
+        BinaryReader {
+            endian: Endian::Big,
+            ..BinaryReader::new(inner)
+        }
+    }
+    /// Changes the order numbers are read in from now on
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+    /// Errors on any read after `limit` bytes
+    pub fn with_limit(inner: R, limit: u64) -> Self {
+        BinaryReader {
+            limit: Some(limit),
+            ..BinaryReader::new(inner)
+        }
+    }
+    /// How many bytes have been read so far
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+    /// How many more bytes can be read before the limit, if there is one
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit - self.position)
+    }
+    /// Reads a `T`.
+    /// If `T` is [FromBinary::STRICT] and there is a limit,
+    /// all of what is left has to be used by it
+    pub fn read_binary<T: FromBinary>(&mut self) -> T {
+        let out = self.endian.scope(|| T::from_binary(self));
+        match self.remaining() {
+            Some(remaining) if T::STRICT && remaining != 0 => left_over::<T>(remaining),
+            _ => out
+        }
+    }
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+impl<R: Read> Read for BinaryReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let buf = match self.remaining() {
+            Some(0) if !buf.is_empty() => return Err(over_limit(self.limit.unwrap())),
+            // Only reading up to the limit, the next read is what errors
+            Some(remaining) => {
+                let len = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+                &mut buf[..len]
+            }
+            None => buf
+        };
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+fn left_over<T>(len: u64) -> ! {
+    panic!("{len} bytes were left over after reading {}, which is strict", std::any::type_name::<T>())
+}
+/// The same as [BinaryReader], but for writing
+pub struct BinaryWriter<W> {
+    inner: W,
+    position: u64,
+    limit: Option<u64>,
+    endian: Endian
+}
+impl<W: Write> BinaryWriter<W> {
+    pub fn new(inner: W) -> Self {
+        BinaryWriter {
+            inner,
+            position: 0,
+            limit: None,
+            endian: Endian::Little
+        }
+    }
+    /// Writes numbers as big endian instead of little
+    pub fn big_endian(inner: W) -> Self {
+        BinaryWriter {
+            endian: Endian::Big,
+            ..BinaryWriter::new(inner)
+        }
+    }
+    /// Changes the order numbers are written in from now on
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+    /// Errors on any write after `limit` bytes
+    pub fn with_limit(inner: W, limit: u64) -> Self {
+        BinaryWriter {
+            limit: Some(limit),
+            ..BinaryWriter::new(inner)
+        }
+    }
+    /// How many bytes have been written so far
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+    /// How many more bytes can be written before the limit, if there is one
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit - self.position)
+    }
+    /// Writes a `T`
+    pub fn write_binary<T: ToBinary + ?Sized>(&mut self, value: &T) {
+        self.endian.scope(|| value.to_binary(self))
+    }
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+impl<W: Write> Write for BinaryWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let buf = match self.remaining() {
+            Some(0) if !buf.is_empty() => return Err(over_limit(self.limit.unwrap())),
+            Some(remaining) => &buf[..buf.len().min(remaining.try_into().unwrap_or(usize::MAX))],
+            None => buf
+        };
+        let written = self.inner.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Works out a CRC32 (the same one as zip and png use) as bytes are given to it,
+/// which is what `#[binary(checksum)]` writes after the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crc32(u32);
+impl Crc32 {
+    pub fn new() -> Self {
+        Crc32(!0)
+    }
+    pub fn update(&mut self, bytes: &[u8]) {
+        // A bit at a time instead of with a table,
+        // since the data is never big enough for it to matter
+        for byte in bytes {
+            self.0 ^= *byte as u32;
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+    pub fn finish(self) -> u32 {
+        !self.0
+    }
+}
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32::new()
+    }
+}
+/// The CRC32 of all of the bytes
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
+/// Reads through to the inner reader,
+/// working out the [Crc32] of everything read
+pub struct Crc32Reader<R> {
+    inner: R,
+    crc: Crc32
+}
+impl<R: Read> Crc32Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Crc32Reader {
+            inner,
+            crc: Crc32::new()
+        }
+    }
+    /// The CRC32 of what has been read so far
+    pub fn checksum(&self) -> u32 {
+        self.crc.finish()
+    }
+}
+impl<R: Read> Read for Crc32Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.crc.update(&buf[..read]);
+        Ok(read)
+    }
+}
+/// Reads through to the inner reader, but can check if there is any more first,
+/// which is how `#[binary(default_on_eof)]` and `#[binary(default_rest)]` know when the fields stop.
+/// The byte read to find out is kept for whatever reads next
+pub struct PeekReader<R> {
+    inner: R,
+    peeked: Option<u8>
+}
+impl<R: Read> PeekReader<R> {
+    pub fn new(inner: R) -> Self {
+        PeekReader {
+            inner,
+            peeked: None
+        }
+    }
+    /// If everything has been read
+    pub fn at_end(&mut self) -> bool {
+        if self.peeked.is_none() {
+            let mut byte = [0_u8; 1];
+            if self.inner.read(&mut byte).expect("failed to read") == 1 {
+                self.peeked = Some(byte[0]);
+            }
+        }
+        self.peeked.is_none()
+    }
+}
+impl<R: Read> Read for PeekReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.peeked, buf.first_mut()) {
+            (Some(byte), Some(first)) => {
+                *first = byte;
+                self.peeked = None;
+                Ok(1)
+            }
+            _ => self.inner.read(buf)
+        }
+    }
+}
+
+/// Run length encodes the bytes, which is what `#[binary(compress)]` writes the rest as.
+/// Each run is written as how many times the byte repeats(1 to 255) and then the byte,
+/// so it only gets smaller when there are a lot of the same byte in a row.
+pub fn rle_encode(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut iter = bytes.iter().peekable();
+    while let Some(byte) = iter.next() {
+        let mut count = 1_u8;
+        while count < u8::MAX && iter.peek() == Some(&byte) {
+            iter.next();
+            count += 1;
+        }
+        out.push(count);
+        out.push(*byte);
+    }
+    out
+}
+/// Undoes [rle_encode], panicking if it isn't something that it could have made
+pub fn rle_decode(bytes: &[u8]) -> Vec<u8> {
+    if !bytes.len().is_multiple_of(2) {
+        panic!("run length encoded data has a run without a byte");
+    }
+    let mut out = Vec::new();
+    for run in bytes.chunks_exact(2) {
+        if run[0] == 0 {
+            panic!("run length encoded data has a run of nothing");
+        }
+        out.extend(std::iter::repeat_n(run[1], run[0] as usize));
+    }
+    out
+}
@@ -0,0 +1,362 @@
+use abes_nice_binary::*;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn round_trip<T: ToBinary + FromBinary>(value: T) -> T {
+    let mut binary = Vec::new();
+    value.to_binary(&mut binary);
+    let mut read = binary.as_slice();
+    let out = T::from_binary(&mut read);
+    assert!(read.is_empty(), "{} bytes were left over", read.len());
+    out
+}
+
+#[test]
+fn hash_map() {
+    let map = HashMap::from([(1_u8, "one".to_string()), (2, "two".to_string())]);
+    assert_eq!(round_trip(map.clone()), map);
+}
+#[test]
+fn btree_map() {
+    let map = BTreeMap::from([(2_u8, 20_u32), (1, 10)]);
+    assert_eq!(round_trip(map.clone()), map);
+    // Always in key order
+    let mut binary = Vec::new();
+    map.to_binary(&mut binary);
+    assert_eq!(binary, [2, 0, 0, 0, 0, 0, 0, 0, 1, 10, 0, 0, 0, 2, 20, 0, 0, 0]);
+}
+#[test]
+fn varint() {
+    for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+        let mut binary = Vec::new();
+        VarInt(value).to_binary(&mut binary);
+        assert_eq!(binary.len(), VarInt(value).binary_size());
+        assert_eq!(VarInt::from_binary(&mut binary.as_slice()), VarInt(value));
+    }
+    let mut binary = Vec::new();
+    VarInt(300).to_binary(&mut binary);
+    assert_eq!(binary, [0b1010_1100, 0b0000_0010]);
+    // The last of the 10 bytes only has the top bit of a u64 in it
+    let mut binary = vec![0xff; 9];
+    binary.push(0x01);
+    assert_eq!(VarInt::from_binary(&mut binary.as_slice()), VarInt(u64::MAX));
+}
+#[test]
+#[should_panic(expected = "varint is too big for a u64")]
+fn varint_too_big() {
+    // Anything above bit 63 doesn't fit, so it can't be dropped quietly
+    let mut binary = vec![0xff; 9];
+    binary.push(0x02);
+    VarInt::from_binary(&mut binary.as_slice());
+}
+#[test]
+fn pointers() {
+    assert_eq!(round_trip(Box::new(5_u32)), Box::new(5));
+    let shared = std::rc::Rc::new("shared".to_string());
+    assert_eq!(round_trip(shared.clone()), shared);
+    let shared = std::sync::Arc::new(vec![1_u8, 2]);
+    // Written the same as what it points to
+    let mut binary = Vec::new();
+    shared.to_binary(&mut binary);
+    assert_eq!(binary.len(), shared.binary_size());
+    assert_eq!(round_trip(shared.clone()), shared);
+}
+#[test]
+fn borrowed() {
+    let owned = vec![1_u16, 2, 3];
+    let mut binary = Vec::new();
+    let view: &[u16] = &owned[..];
+    <&[u16] as ToBinary>::to_binary(&view, &mut binary);
+    assert_eq!(binary, owned.to_binary_vec());
+    assert_eq!(<&[u16] as BinarySize>::binary_size(&view), binary.len());
+    assert_eq!(Vec::<u16>::from_binary_slice(&binary), owned);
+
+    let owned = "borrowed".to_string();
+    assert_eq!("borrowed".to_binary_vec(), owned.to_binary_vec());
+    assert_eq!(<&String as ToBinary>::to_binary_vec(&&owned), owned.to_binary_vec());
+    assert_eq!("borrowed".binary_size(), owned.binary_size());
+}
+#[test]
+fn reader_position() {
+    let binary = "two".to_binary_vec();
+    let mut reader = BinaryReader::new(binary.as_slice());
+    assert_eq!(reader.read_binary::<String>(), "two");
+    assert_eq!(reader.position(), 11);
+    assert_eq!(reader.remaining(), None);
+}
+#[test]
+fn reader_in_limit() {
+    let binary = [1, 0, 0, 0, 2];
+    let mut reader = BinaryReader::with_limit(binary.as_slice(), 5);
+    assert_eq!(reader.read_binary::<u32>(), 1);
+    assert_eq!(reader.remaining(), Some(1));
+    assert_eq!(reader.read_binary::<u8>(), 2);
+    assert_eq!(reader.remaining(), Some(0));
+}
+#[test]
+#[should_panic(expected = "went past the limit of 3 bytes")]
+fn reader_over_limit() {
+    // There is enough data, but the limit stops it from being read
+    let binary = [1, 0, 0, 0];
+    BinaryReader::with_limit(binary.as_slice(), 3).read_binary::<u32>();
+}
+#[test]
+#[should_panic(expected = "went past the limit of 10 bytes")]
+fn reader_limits_length() {
+    // A length that is too long gets stopped instead of reading everything
+    let length = 1000_u64.to_binary_vec();
+    let data = std::io::Read::chain(length.as_slice(), std::io::repeat(0));
+    BinaryReader::with_limit(data, 10).read_binary::<Vec<u8>>();
+}
+#[test]
+#[should_panic(expected = "went past the limit of 10 bytes")]
+fn reader_limits_huge_length() {
+    // Making room for all of it up front would fail to allocate instead
+    let length = (1_u64 << 50).to_binary_vec();
+    let data = std::io::Read::chain(length.as_slice(), std::io::repeat(0));
+    BinaryReader::with_limit(data, 10).read_binary::<Vec<u64>>();
+}
+#[test]
+#[should_panic(expected = "went past the limit of 10 bytes")]
+fn reader_limits_huge_map_length() {
+    let length = (1_u64 << 50).to_binary_vec();
+    let data = std::io::Read::chain(length.as_slice(), std::io::repeat(0));
+    BinaryReader::with_limit(data, 10).read_binary::<HashMap<u64, u64>>();
+}
+#[test]
+fn writer_limit() {
+    let mut writer = BinaryWriter::with_limit(Vec::new(), 4);
+    writer.write_binary(&7_u32);
+    assert_eq!(writer.position(), 4);
+    assert_eq!(writer.into_inner(), [7, 0, 0, 0]);
+
+    let mut writer = BinaryWriter::with_limit(Vec::new(), 4);
+    let error = std::io::Write::write_all(&mut writer, &[0; 5]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(writer.position(), 4);
+}
+#[test]
+fn big_endian() {
+    let mut writer = BinaryWriter::big_endian(Vec::new());
+    writer.write_binary(&0x0102_u16);
+    writer.write_binary(&vec![1.5_f32]);
+    writer.write_binary(&-2_i64);
+    let binary = writer.into_inner();
+    let mut expected = vec![1, 2];
+    expected.extend_from_slice(&1_u64.to_be_bytes());
+    expected.extend_from_slice(&1.5_f32.to_be_bytes());
+    expected.extend_from_slice(&(-2_i64).to_be_bytes());
+    assert_eq!(binary, expected);
+    let mut reader = BinaryReader::big_endian(binary.as_slice());
+    assert_eq!(reader.read_binary::<u16>(), 0x0102);
+    assert_eq!(reader.read_binary::<Vec<f32>>(), [1.5]);
+    assert_eq!(reader.read_binary::<i64>(), -2);
+    // It only lasts as long as the read or write
+    assert_eq!(Endian::current(), Endian::Little);
+    assert_eq!(0x0102_u16.to_binary_vec(), [2, 1]);
+}
+#[test]
+fn arrays() {
+    let array = [1_u16, 2, 3];
+    assert_eq!(array.to_binary_vec(), [1, 0, 2, 0, 3, 0]);
+    assert_eq!(<[u16; 3]>::from_binary_slice(&array.to_binary_vec()), array);
+    assert_eq!(array.binary_size(), 6);
+    assert_eq!(<[[u16; 3]; 2]>::SERIALIZED_SIZE, 12);
+    assert_eq!(<[u8; 0]>::from_binary_slice(&[]), []);
+    let mut strings = [String::new(), String::with_capacity(100)];
+    strings.from_binary_into(&mut ["a", "b"].map(str::to_string).to_binary_vec().as_slice());
+    assert_eq!(strings, ["a", "b"]);
+    assert!(strings[1].capacity() >= 100);
+}
+#[test]
+fn floats() {
+    for value in [1.5_f64, -0.1, f64::MIN_POSITIVE, f64::MAX] {
+        assert_eq!(f64::from_binary_slice(&value.to_binary_vec()), value);
+    }
+    let negative_zero = f32::from_binary_slice(&(-0.0_f32).to_binary_vec());
+    assert!(negative_zero == 0.0 && negative_zero.is_sign_negative());
+    assert_eq!(f32::from_binary_slice(&f32::INFINITY.to_binary_vec()), f32::INFINITY);
+    assert_eq!(f64::from_binary_slice(&f64::NEG_INFINITY.to_binary_vec()), f64::NEG_INFINITY);
+    // A NaN with a payload comes back bit for bit
+    let nan = f32::from_bits(0x7fc0_1234);
+    assert_eq!(nan.to_binary_vec(), [0x34, 0x12, 0xc0, 0x7f]);
+    assert_eq!(f32::from_binary_slice(&nan.to_binary_vec()).to_bits(), 0x7fc0_1234);
+    let nan = f64::from_bits(0xfff8_0000_dead_beef);
+    assert_eq!(f64::from_binary_slice(&nan.to_binary_vec()).to_bits(), 0xfff8_0000_dead_beef);
+    let mut writer = BinaryWriter::big_endian(Vec::new());
+    writer.write_binary(&nan);
+    assert_eq!(writer.into_inner(), 0xfff8_0000_dead_beef_u64.to_be_bytes());
+}
+#[test]
+fn set_endian() {
+    let mut writer = BinaryWriter::new(Vec::new());
+    writer.write_binary(&1_u16);
+    writer.set_endian(Endian::Big);
+    writer.write_binary(&1_u16);
+    assert_eq!(writer.into_inner(), [1, 0, 0, 1]);
+}
+#[test]
+fn checksum() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(&[]), 0);
+    // Reading in pieces gives the same as all at once
+    let mut reader = Crc32Reader::new(&b"123456789"[..]);
+    assert_eq!(u32::from_binary(&mut reader), u32::from_le_bytes(*b"1234"));
+    std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+    assert_eq!(reader.checksum(), 0xCBF4_3926);
+}
+#[test]
+fn char() {
+    for c in ['a', 'é', '🦀', '\0', char::MAX] {
+        assert_eq!(round_trip(c), c);
+        assert_eq!(c.binary_size(), 4);
+    }
+    assert_eq!('🦀'.to_binary_vec(), 0x1F980_u32.to_le_bytes());
+}
+#[test]
+#[should_panic(expected = "invalid char: 0xd800")]
+fn invalid_char() {
+    // A surrogate, which isn't allowed in a char
+    char::from_binary_slice(&0xD800_u32.to_le_bytes());
+}
+#[test]
+fn duration() {
+    for duration in [Duration::ZERO, Duration::from_millis(1500), Duration::from_nanos(7), Duration::MAX] {
+        assert_eq!(round_trip(duration), duration);
+        assert_eq!(duration.binary_size(), 12);
+    }
+    let mut expected = 1_u64.to_le_bytes().to_vec();
+    expected.extend_from_slice(&500_000_000_u32.to_le_bytes());
+    assert_eq!(Duration::from_millis(1500).to_binary_vec(), expected);
+}
+#[test]
+#[should_panic(expected = "invalid nanoseconds in Duration: 1000000000")]
+fn invalid_duration() {
+    let mut binary = 0_u64.to_le_bytes().to_vec();
+    binary.extend_from_slice(&1_000_000_000_u32.to_le_bytes());
+    Duration::from_binary_slice(&binary);
+}
+#[test]
+fn system_time() {
+    let now = SystemTime::now();
+    assert_eq!(round_trip(now), now);
+    assert_eq!(round_trip(UNIX_EPOCH), UNIX_EPOCH);
+    assert_eq!(UNIX_EPOCH.to_binary_vec(), [0; 12]);
+}
+#[test]
+#[should_panic(expected = "before the UNIX_EPOCH")]
+fn before_epoch() {
+    (UNIX_EPOCH - Duration::from_secs(1)).to_binary_vec();
+}
+#[test]
+fn non_zero() {
+    use std::num::{NonZeroI16, NonZeroU32, NonZeroUsize};
+    let value = NonZeroU32::new(0x01020304).unwrap();
+    assert_eq!(round_trip(value), value);
+    assert_eq!(value.to_binary_vec(), [4, 3, 2, 1]);
+    assert_eq!(value.binary_size(), 4);
+    let value = NonZeroI16::new(-5).unwrap();
+    assert_eq!(round_trip(value), value);
+    // Written as 64 bits, the same as usize
+    let value = NonZeroUsize::new(1).unwrap();
+    assert_eq!(value.to_binary_vec().len(), 8);
+    assert_eq!(round_trip(Some(value)), Some(value));
+}
+#[test]
+fn result() {
+    let ok: Result<u16, String> = Ok(3);
+    assert_eq!(ok.to_binary_vec(), [0, 3, 0]);
+    assert_eq!(ok.binary_size(), 3);
+    assert_eq!(round_trip(ok.clone()), ok);
+    let err: Result<u16, String> = Err("bad".to_string());
+    assert_eq!(err.to_binary_vec()[0], 1);
+    assert_eq!(err.to_binary_vec().len(), err.binary_size());
+    assert_eq!(round_trip(err.clone()), err);
+}
+#[test]
+#[should_panic(expected = "invalid Result tag: 2")]
+fn invalid_result() {
+    _ = Result::<u8, u8>::from_binary_slice(&[2, 0]);
+}
+#[test]
+fn cow() {
+    use std::borrow::Cow;
+    let borrowed: Cow<str> = Cow::Borrowed("cow");
+    let owned: Cow<str> = Cow::Owned("cow".to_string());
+    assert_eq!(borrowed.to_binary_vec(), owned.to_binary_vec());
+    assert_eq!(borrowed.to_binary_vec(), "cow".to_binary_vec());
+    assert_eq!(borrowed.binary_size(), 11);
+    assert!(matches!(Cow::<str>::from_binary_slice(&borrowed.to_binary_vec()), Cow::Owned(read) if read == "cow"));
+    let bytes: Cow<[u16]> = Cow::Borrowed(&[1, 2]);
+    assert_eq!(bytes.to_binary_vec(), vec![1_u16, 2].to_binary_vec());
+    assert!(matches!(round_trip(bytes), Cow::Owned(read) if read == [1, 2]));
+}
+#[test]
+fn paths() {
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+    let path = PathBuf::from("dir/file name.txt");
+    assert_eq!(path.to_binary_vec(), "dir/file name.txt".to_binary_vec());
+    assert_eq!(Path::new("dir/file name.txt").to_binary_vec(), path.to_binary_vec());
+    assert_eq!(path.binary_size(), 8 + 17);
+    assert_eq!(round_trip(path.clone()), path);
+    assert_eq!(round_trip(OsString::from("ünïcode")), OsString::from("ünïcode"));
+    assert_eq!(PathBuf::from_binary_slice(&"written as a string".to_binary_vec()), Path::new("written as a string"));
+}
+// Only unix can have paths that aren't UTF-8 come back the same,
+// everywhere else they get written with � in place of what isn't
+#[cfg(unix)]
+#[test]
+fn non_utf8_path() {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::path::Path::new(std::ffi::OsStr::from_bytes(b"bad\xff"));
+    assert_eq!(path.to_binary_vec()[8..], *b"bad\xff");
+    assert_eq!(round_trip(path.to_path_buf()), path);
+}
+#[test]
+fn socket_addr() {
+    let v4: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    assert_eq!(v4.to_binary_vec(), [4, 127, 0, 0, 1, 0x90, 0x1f]);
+    assert_eq!(v4.binary_size(), 7);
+    assert_eq!(round_trip(v4), v4);
+    let v6: SocketAddr = "[2001:db8::1]:443".parse().unwrap();
+    assert_eq!(v6.to_binary_vec().len(), v6.binary_size());
+    assert_eq!(v6.binary_size(), 19);
+    assert_eq!(round_trip(v6), v6);
+    assert_eq!(round_trip(Ipv4Addr::LOCALHOST), Ipv4Addr::LOCALHOST);
+    assert_eq!(Ipv6Addr::LOCALHOST.to_binary_vec()[15], 1);
+    assert_eq!(round_trip(IpAddr::V6(Ipv6Addr::UNSPECIFIED)), IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+}
+#[test]
+#[should_panic(expected = "invalid IpAddr version: 5")]
+fn bad_ip_version() {
+    IpAddr::from_binary_slice(&[5, 0, 0, 0, 0]);
+}
+#[test]
+#[should_panic(expected = "zero can't be read as a NonZeroU32")]
+fn zero_non_zero() {
+    std::num::NonZeroU32::from_binary_slice(&[0, 0, 0, 0]);
+}
+#[test]
+fn unit() {
+    assert!(().to_binary_vec().is_empty());
+    assert_eq!(().binary_size(), 0);
+    assert_eq!(round_trip(vec![(), ()]), vec![(), ()]);
+}
+#[test]
+fn rle() {
+    assert!(rle_encode(&[]).is_empty());
+    assert_eq!(rle_encode(&[5, 5, 5, 1]), [3, 5, 1, 1]);
+    // Runs longer than 255 get split up
+    let long = vec![9; 300];
+    assert_eq!(rle_encode(&long), [255, 9, 45, 9]);
+    assert_eq!(rle_decode(&rle_encode(&long)), long);
+    let mixed = b"aaaabccccccd".to_vec();
+    assert_eq!(rle_decode(&rle_encode(&mixed)), mixed);
+}
+#[test]
+#[should_panic(expected = "run without a byte")]
+fn rle_odd() {
+    rle_decode(&[3, 5, 1]);
+}
@@ -1,5 +1,6 @@
 use proc_macro::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(serde::Deserialize)]
 struct CargoManifest {
@@ -13,17 +14,162 @@ struct CargoPackage {
 
 struct DeleteOnDrop<P: AsRef<Path>> {
     path: P,
+    keep: bool,
 }
 impl<P: AsRef<Path>> DeleteOnDrop<P> {
-    fn new(path: P) -> Self {
+    fn new(path: P, keep: bool) -> Self {
         DeleteOnDrop {
-            path
+            path,
+            keep
         }
     }
 }
 impl<P: AsRef<Path>> Drop for DeleteOnDrop<P> {
     fn drop(&mut self) {
-        _ = std::fs::remove_file(self.path.as_ref());
+        let path = self.path.as_ref();
+        if !self.keep {
+            if path.is_dir() {
+                _ = std::fs::remove_dir_all(path);
+            }
+            else {
+                _ = std::fs::remove_file(path);
+            }
+        }
+        else if path.exists() {
+            eprintln!("note: kept {}", path.display());
+        }
+    }
+}
+
+/// Settings for how [run] compiles and runs the code
+#[derive(Default)]
+struct Options {
+    /// Stops the files from being deleted so they can be looked at,
+    /// set by ABES_PROCS_KEEP
+    keep: bool,
+    /// What to compile with instead of rustc,
+    /// set by RUSTC
+    rustc: Option<String>,
+    /// How long the binary gets to run before it is stopped,
+    /// set by `timeout_ms = ...`
+    timeout: Option<Duration>,
+    /// What builds the code,
+    /// cargo when the cargo-driver feature is on
+    driver: Driver,
+    /// The rustup toolchain to build with instead of the default one,
+    /// set by `toolchain = "..."`
+    toolchain: Option<String>,
+    /// Formats the output with rustfmt, so it is easier to read when debugging,
+    /// set by `fmt = true`
+    fmt: bool,
+    /// Gives back what it printed encoded instead of needing it to be utf8,
+    /// set by `encode = "..."`
+    encode: Option<Encoding>,
+    /// Shares one run between every call with the same key and code,
+    /// set by `cache_key = "..."`
+    cache_key: Option<String>,
+    /// Leaves the newline at the end of the output instead of taking it off,
+    /// set by `trim = false`
+    keep_newline: bool,
+    /// Where to save the code and what it printed, so runs can be compared,
+    /// set by ABES_PROCS_RECORD_DIR
+    record_dir: Option<PathBuf>,
+    /// Runs the binary with RUST_BACKTRACE=1 so panics say how they got there,
+    /// set by ABES_PROCS_BACKTRACE
+    backtrace: bool,
+    /// Extra flags given to rustc, which are the RUSTFLAGS
+    /// when there is `rustflags = "inherit"`
+    rustflags: Vec<String>,
+    /// Gives back the exit code instead of what it printed,
+    /// without it not being 0 being an error, for method_status!
+    status: bool,
+    /// Where the files go instead of the crate root, which keeps them
+    /// so they can be used again by later builds,
+    /// set by `build_dir = "..."`
+    build_dir: Option<PathBuf>,
+    /// Runs the binary with only the environment variables in it,
+    /// set by `env_clear = true` and `env_allow = [...]`
+    env_allow: Option<Vec<String>>,
+}
+impl Options {
+    fn from_env() -> Options {
+        Options {
+            keep: flag(std::env::var("ABES_PROCS_KEEP").ok()),
+            backtrace: flag(std::env::var("ABES_PROCS_BACKTRACE").ok()),
+            rustc: std::env::var("RUSTC").ok().filter(|rustc| !rustc.is_empty()),
+            record_dir: std::env::var_os("ABES_PROCS_RECORD_DIR").filter(|dir| !dir.is_empty()).map(PathBuf::from),
+            driver: if cfg!(feature = "cargo-driver") { Driver::Cargo } else { Driver::Rustc },
+            ..Default::default()
+        }
+    }
+    /// What rustc is called in errors
+    fn rustc_name(&self) -> &str {
+        match &self.toolchain {
+            Some(_) => "rustc",
+            None => self.rustc.as_deref().unwrap_or("rustc")
+        }
+    }
+    /// Makes the command for running rustc,
+    /// which goes through rustup when there is a toolchain
+    /// (the rustc from RUSTC is ignored then, since it is from a different toolchain)
+    fn rustc_command(&self) -> std::process::Command {
+        match &self.toolchain {
+            Some(toolchain) => rustup_run(toolchain, "rustc"),
+            None => std::process::Command::new(self.rustc_name())
+        }
+    }
+    /// The same as [Options::rustc_command], but for cargo (from CARGO when there is no toolchain)
+    fn cargo_command(&self) -> std::process::Command {
+        match &self.toolchain {
+            Some(toolchain) => rustup_run(toolchain, "cargo"),
+            None => std::process::Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        }
+    }
+}
+fn rustup_run(toolchain: &str, tool: &str) -> std::process::Command {
+    let mut command = std::process::Command::new("rustup");
+    command.arg("run").arg(toolchain).arg(tool);
+    command
+}
+#[derive(Default, Clone, Copy)]
+enum Driver {
+    /// Giving the file straight to rustc, which is quicker
+    #[default]
+    Rustc,
+    /// Making a crate for the file and building it with cargo,
+    /// so it gets everything a normal crate would
+    Cargo
+}
+#[derive(Clone, Copy)]
+enum Encoding {
+    Hex,
+    Base64
+}
+impl Encoding {
+    fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Hex => bytes.iter().map(|byte| format!("{byte:02x}")).collect(),
+            Encoding::Base64 => {
+                const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+                let mut out = String::new();
+                for chunk in bytes.chunks(3) {
+                    // Every 3 bytes become 4 characters of 6 bits each,
+                    // with = for the ones a short chunk doesn't fill
+                    let mut group = [0_u8; 3];
+                    group[..chunk.len()].copy_from_slice(chunk);
+                    let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+                    for index in 0..4 {
+                        if index <= chunk.len() {
+                            out.push(ALPHABET[((bits >> (18 - 6 * index)) & 63) as usize] as char);
+                        }
+                        else {
+                            out.push('=');
+                        }
+                    }
+                }
+                out
+            }
+        }
     }
 }
 
@@ -45,22 +191,23 @@ impl<P: AsRef<Path>> Drop for DeleteOnDrop<P> {
 /// are pre-existing file with that name, they will
 /// be overwritten. If you don't want that to happen,
 /// I suggest giving them a unique name.
-/// (important side note: because this is a macro,
-/// it takes in the code you give it as is, meaning
-/// that if you give do method("file_name", code),
-/// it will fail because idk, it doesn't like having
-/// a file called "file_name".rs)
+/// It can be given as a string too, for names
+/// that aren't identifiers, but it can only have
+/// letters, numbers, _, and - in it either way.
 ///```
 /// # use abes_nice_procs::method;
 /// # fn main() {
-/// method!(file_name, fn main() {})
+/// method!(file_name, fn main() {});
 /// // Creates file_name.rs and file_name
 /// //          ^^^ provided code  ^^^ binary
+/// method!("file-name", fn main() {})
+/// // Creates file-name.rs and file-name
 /// # }
 ///```
 /// ### Code
 /// The code is placed into the file directly as given,
-/// meaning it needs a main function to be placed in it.
+/// meaning it needs a main function to be placed in it
+/// (unless it doesn't have one, more on that below).
 /// The best way to think of it(because this is what happens)
 /// is as if you are making a main file, meaning that it
 /// needs all the things associated with that.
@@ -80,6 +227,15 @@ impl<P: AsRef<Path>> Drop for DeleteOnDrop<P> {
 /// )
 /// # } 
 ///```
+/// If there is no main function, the code is put in one,
+/// which is handy when there is only a little bit of it.
+/// Inner attributes at the start, like `#![allow(unused)]`, stay outside of it.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example_no_main, print!("{}", 2 + 2)), 4);
+/// # }
+///```
 /// This wouldn't be a macro if it didn't create code.
 /// The way this actually determines gets the code is weird.
 /// It uses the stdout of the code given to it.
@@ -102,6 +258,11 @@ impl<P: AsRef<Path>> Drop for DeleteOnDrop<P> {
 /// This is because the output of the macro is given as code.
 /// Meaning that the compiler saw the integer literal 5
 /// and acted accordingly.
+///
+/// One newline at the end of what it prints gets taken off,
+/// so println! gives the same thing as print!.
+/// Giving `trim = false` before the code keeps it,
+/// which only really matters for [method_str!].
 /// 
 /// Similarly, you could
 ///```
@@ -119,57 +280,1310 @@ impl<P: AsRef<Path>> Drop for DeleteOnDrop<P> {
 /// when trying to put a [String] in,
 /// the quotes will still be removed.
 /// But that can be bypassed by escaping out the quotes.
+///
+/// ### Include
+/// Instead of giving the code directly,
+/// it can be read from a file (relative to the crate root)
+/// by giving `include = "path"` in place of the code.
+/// The macro gets expanded again whenever the file changes.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example4, include = "tests/generators/seven.rs"), 7);
+/// # }
+///```
+///
+/// ### Params
+/// The same code can be run with different constants by giving them
+/// as `{ NAME = value, ... }` right after the file name,
+/// which puts a `const NAME: Type = value;` at the end of the code.
+/// The type is guessed for strings, chars, bools, and numbers
+/// (i64 and f64, unless they have a suffix like `5u8`),
+/// anything else needs it given like `{ NAME: Type = value }`.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example_params, { COUNT = 3, SEPARATOR = "-" },
+///     print!("\"{}\"", vec!["ab"; COUNT as usize].join(SEPARATOR))
+/// ), "ab-ab-ab");
+/// # }
+///```
+///
+/// ### Dependencies
+/// If the code reads any files, cargo won't know to
+/// expand the macro again when they change.
+/// Giving them as `deps = ["file", ...]` (relative to the crate root)
+/// before the code fixes that.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example5, deps = ["Cargo.toml"],
+///     fn main() {
+///         print!("{}", std::fs::read_to_string("Cargo.toml").unwrap().len() > 0)
+///     }
+/// ), true);
+/// # }
+///```
+///
+/// ### Timeout
+/// Code that never finishes would stop the build from ever finishing,
+/// so `timeout_ms = ...` can be given before the code
+/// to stop it after that many milliseconds,
+/// which gives a compile error instead.
+/// There is no timeout if it isn't given.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example6, timeout_ms = 60000, print!("5")), 5);
+/// # }
+///```
+///
+/// ### Toolchain
+/// Code that needs a nightly feature can be built with
+/// a different toolchain through rustup,
+/// by giving `toolchain = "..."` before the code.
+/// It still uses the edition of this crate.
+///```ignore
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example7, toolchain = "nightly",
+///     #![feature(never_type)]
+///     fn main() {
+///         let never: Option<!> = None;
+///         print!("{}", never.is_none());
+///     }
+/// ), true);
+/// # }
+///```
+///
+/// ### Formatting
+/// Giving `fmt = true` before the code runs what it prints through rustfmt,
+/// which doesn't change what it does, but makes it easier to read
+/// when it ends up in an error or something like cargo expand.
+/// Only items can be formatted, so anything else(or not having rustfmt)
+/// just leaves it how it was, with a note in the build output.
+///```
+/// # use abes_nice_procs::method;
+/// method!(example8, fmt = true, print!("fn eight()->u8{{8}}"));
+/// # fn main() {
+/// assert_eq!(eight(), 8);
+/// # }
+///```
+///
+/// ### Sharing
+/// When the same slow code is used in a few places,
+/// giving them the same `cache_key = "..."` only compiles and runs it for the first one,
+/// with the rest using what it printed then.
+/// That means it has to print the same thing every time,
+/// since it isn't run again to find out if it would be different.
+/// The code has to be the same for every call with the key, otherwise it is an error,
+/// and it only lasts for this build of the crate.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// let first = method!(example9, cache_key = "example", print!("{}", (1..=10).product::<u64>()));
+/// let second = method!(example10, cache_key = "example", print!("{}", (1..=10).product::<u64>()));
+/// assert_eq!(first, second);
+/// # }
+///```
+///
+/// ### Environment
+/// The code normally gets every environment variable the compiler has,
+/// which makes it easy to depend on them by accident.
+/// Giving `env_clear = true` runs it without any,
+/// except for the ones given with `env_allow = ["NAME", ...]`.
+/// This isn't a sandbox, the code can still do anything else.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example_env, env_clear = true, env_allow = ["CARGO_PKG_NAME"],
+///     fn main() {
+///         print!("{}", std::env::var("CARGO_PKG_NAME").is_ok() && std::env::var("CARGO_MANIFEST_DIR").is_err())
+///     }
+/// ), true);
+/// # }
+///```
+///
+/// ### Build directory
+/// Normally the files are made in the crate root and deleted after.
+/// Giving `build_dir = "..."` (relative to the crate root) makes them there instead,
+/// and keeps them, so a later build with the same code doesn't have to compile it again.
+/// A `target` at the start of it is CARGO_TARGET_DIR if that is set,
+/// so `build_dir = "target/abes_procs"` gets cleaned up by `cargo clean`.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// assert_eq!(method!(example_build_dir, build_dir = "target/abes_procs", print!("5")), 5);
+/// # }
+///```
+///
+/// ### Debugging
+/// If the code isn't doing what it should,
+/// setting the ABES_PROCS_KEEP environment variable to 1
+/// stops the files from being deleted,
+/// so you can look at the .rs file and run the binary yourself.
+/// The kept files get listed in the build output.
+///
+/// Only what the code prints to stdout is used as code,
+/// so anything printed to stderr (like with eprintln!) can be used for logging.
+/// It shows up in the build output as the code runs
+/// (for a dependency, cargo only shows that with `-vv`).
+///
+/// If the code panics, the error has what it printed to stderr,
+/// which includes where it panicked.
+/// Setting ABES_PROCS_BACKTRACE to 1 runs it with RUST_BACKTRACE=1 too,
+/// so the error also says how it got there.
+///
+/// To check that the code prints the same thing every build,
+/// the ABES_PROCS_RECORD_DIR environment variable can be set to a directory
+/// that each call saves its code to as (name).rs,
+/// and what it printed to as (name).out.
+/// Those don't change names between builds, so they can be compared,
+/// such as in CI.
+/// Cargo knows the calls depend on all of these,
+/// so setting or changing one builds them again.
+///
+/// ### Skipping
+/// Compiling and running the code every time something like rust-analyzer
+/// or `cargo check` looks at the crate can make them slow.
+/// Setting ABES_PROCS_SKIP to 1 (like in rust-analyzer's `cargo.extraEnv`)
+/// stops that, so each call becomes a stand in instead:
+/// `Default::default()` for [method!], `""` for [method_str!],
+/// `0` for [method_status!], and nothing for [method_items!] and [static_run_assert!].
+/// Without running it, [method!] can't know if the code prints items,
+/// so it guesses from the first string in the code, like `print!("fn ...")`,
+/// and is nothing then too.
+/// The code isn't checked at all then, so it shouldn't be set for real builds.
+/// Cargo knows every call depends on it, so setting or unsetting it builds them again.
+/// ABES_PROCS_SKIP is the only thing that turns this on,
+/// so it has to be set even when something like RUST_ANALYZER is.
+///
+/// ### Compiler
+/// The code is compiled with the rustc on the PATH,
+/// unless the RUSTC environment variable says to use a different one.
+/// It is always built for the machine doing the compiling
+/// (from HOST, or what rustc says it is),
+/// because that is where it runs, even when cross compiling.
+/// Anything going wrong while compiling or running the code
+/// becomes a compile error where the macro was used.
+///
+/// With the cargo-driver feature, the code is instead made into
+/// a tiny crate in a (name) folder, with the same edition as this one,
+/// and built by cargo (from CARGO, otherwise the one on the PATH).
+/// That is slower, but it gets built the way a normal crate would.
+///
+/// RUSTFLAGS are for the crate being built, not the code here,
+/// and things like a `-C target-cpu` might not even work for it,
+/// so they aren't used unless `rustflags = "inherit"` is given before the code.
+/// `rustflags = "ignore"` is the same as not giving it.
+///```
+/// # use abes_nice_procs::method;
+/// # fn main() {
+/// // true if RUSTFLAGS has `--cfg abes_flag` in it
+/// let _flagged: bool = method!(example_flags, rustflags = "inherit", print!("{}", cfg!(abes_flag)));
+/// # }
+///```
 pub fn method(attr: TokenStream) -> TokenStream {
+    expand(attr, Output::Tokens)
+}
+#[proc_macro]
+/// The same as [method!], except that what the code prints
+/// is given as a string literal instead of being used as code.
+/// This is useful for embedding text that was made at compile time,
+/// since nothing in it needs to be escaped.
+///```
+/// # use abes_nice_procs::method_str;
+/// # fn main() {
+/// assert_eq!(method_str!(example_str,
+///     fn main() {
+///         print!("Hello \"world\"");
+///     }
+/// ), "Hello \"world\"");
+/// # }
+///```
+/// If what it prints isn't text, `encode = "hex"` or `encode = "base64"`
+/// gives back the bytes encoded, so they don't need to be utf8.
+/// (fmt doesn't do anything with it)
+///```
+/// # use abes_nice_procs::method_str;
+/// # fn main() {
+/// assert_eq!(method_str!(example_hex, encode = "hex",
+///     use std::io::Write;
+///     std::io::stdout().write_all(&[0, 255, 16]).unwrap();
+/// ), "00ff10");
+/// # }
+///```
+pub fn method_str(attr: TokenStream) -> TokenStream {
+    expand(attr, Output::Str)
+}
+#[proc_macro]
+/// The same as [method!], except that what the code prints
+/// is always used as items, like functions, structs, and impls.
+/// [method!] guesses from how the output starts,
+/// which this doesn't need to do, so it is better for when
+/// the output is only ever going to be items.
+///```
+/// # use abes_nice_procs::method_items;
+/// method_items!(example_items,
+///     print!("fn generated() -> u8 {{ 7 }}");
+///     print!("struct Generated;");
+///     print!("impl Generated {{ fn value(&self) -> u8 {{ generated() + 1 }} }}");
+/// );
+/// # fn main() {
+/// assert_eq!(generated(), 7);
+/// assert_eq!(Generated.value(), 8);
+/// # }
+///```
+pub fn method_items(attr: TokenStream) -> TokenStream {
+    expand(attr, Output::Items)
+}
+#[proc_macro]
+/// The same as [method!], except that it gives back the exit code
+/// of the code as an integer literal, instead of what it printed.
+/// It not being 0 isn't an error either,
+/// so it can be used to check for things without stopping the build,
+/// but the code still has to compile.
+///```
+/// # use abes_nice_procs::method_status;
+/// # fn main() {
+/// assert_eq!(method_status!(example_status, std::process::exit(3)), 3);
+/// assert_eq!(method_status!(example_success, print!("ignored")), 0);
+/// # }
+///```
+pub fn method_status(attr: TokenStream) -> TokenStream {
+    expand(attr, Output::Status)
+}
+#[proc_macro]
+/// Compiles and runs the code the same way as [method!],
+/// but only to check that it works, so it becomes nothing.
+/// If it panics (or exits with anything other than 0),
+/// the build fails with what it printed to stderr.
+/// That makes it good for checking things about where it is being built.
+///```
+/// # use abes_nice_procs::static_run_assert;
+/// static_run_assert!(example_assert,
+///     fn main() {
+///         assert!(std::path::Path::new("Cargo.toml").exists());
+///     }
+/// );
+/// # fn main() {}
+///```
+///```compile_fail
+/// # use abes_nice_procs::static_run_assert;
+/// static_run_assert!(example_assert_fails, assert_eq!(1 + 1, 3));
+/// # fn main() {}
+///```
+pub fn static_run_assert(attr: TokenStream) -> TokenStream {
+    expand(attr, Output::Nothing)
+}
+#[proc_macro]
+/// Runs several [method!] calls at once,
+/// giving back a tuple of what each one printed.
+/// Each call goes in its own parentheses,
+/// and takes the same things a [method!] call would.
+/// Since they are put in a tuple, each one has to print an expression.
+///```
+/// # use abes_nice_procs::methods;
+/// # fn main() {
+/// let (a, b) = methods!(
+///     (example_a, print!("1 + 1")),
+///     (example_b, fn main() { print!("\"two\"") })
+/// );
+/// assert_eq!(a, 2);
+/// assert_eq!(b, "two");
+/// # }
+///```
+/// The programs are compiled and run in parallel,
+/// which is a lot faster than one [method!] after the other
+/// when there are a few of them.
+/// By default, as many get built at once as there are cores,
+/// but the ABES_PROCS_JOBS environment variable can set it to something else,
+/// which builds them again when it changes.
+/// The file names still need to be different from each other,
+/// since they are all around at the same time.
+pub fn methods(attr: TokenStream) -> TokenStream {
+    let usage = "methods! is used like methods!((file_name, code), (file_name, code))";
+    let mut calls = Vec::new();
+    for tree in attr {
+        match tree {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                match parse_call(group.stream(), usage) {
+                    Ok(call) => calls.push((call, group.span())),
+                    Err(error) => return error
+                }
+            }
+            TokenTree::Punct(punct) if punct.as_char() == ',' => {}
+            other => return compile_error(&format!("expected a call in (). {usage}"), other.span())
+        }
+    }
+    for (index, (call, span)) in calls.iter().enumerate() {
+        if calls[..index].iter().any(|(other, _)| other.path == call.path) {
+            return compile_error(&format!("{} is used more than once", call.path), *span)
+        }
+    }
+    if let Some(stub) = stub(std::env::var("ABES_PROCS_SKIP").ok(), &Output::Tokens, false) {
+        return format!("({})", format!("{stub},").repeat(calls.len())).parse().unwrap()
+    }
+    let jobs = match job_limit(std::env::var("ABES_PROCS_JOBS").ok()) {
+        Ok(jobs) => jobs,
+        Err(error) => return compile_error(&error, Span::call_site())
+    };
+    let results = run_all(&calls.iter().map(|(call, _)| call).collect::<Vec<&Call>>(), jobs);
+    let mut outputs = String::new();
+    let mut tracked = Vec::new();
+    for ((call, span), result) in calls.iter().zip(results) {
+        match result {
+            Ok(output) => {
+                outputs.push_str(&output);
+                outputs.push(',');
+            }
+            Err(error) => return compile_error(&error, *span)
+        }
+        tracked.extend(call.tracked.iter().cloned());
+    }
+    track(&tracked, &format!("({outputs})"), false)
+        .parse::<TokenStream>()
+        .unwrap()
+}
+/// How many programs methods! can build at once, from what ABES_PROCS_JOBS is set to
+fn job_limit(jobs: Option<String>) -> Result<usize, String> {
+    match jobs {
+        Some(jobs) => jobs.parse::<usize>()
+            .ok()
+            .filter(|jobs| *jobs > 0)
+            .ok_or_else(|| format!("ABES_PROCS_JOBS should be a number above 0, not {jobs:?}")),
+        None => Ok(std::thread::available_parallelism()
+            .map(|jobs| jobs.get())
+            .unwrap_or(1))
+    }
+}
+/// Runs all of the calls, with up to jobs of them going at once.
+/// The results are in the same order as the calls.
+fn run_all(calls: &[&Call], jobs: usize) -> Vec<Result<String, String>> {
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let results = std::sync::Mutex::new(vec![None; calls.len()]);
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(calls.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some(call) = calls.get(index) else {
+                    break
+                };
+                let result = run_cached(call);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    results.into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every call should have been run"))
+        .collect()
+}
+/// What the output of the code gets turned into
+enum Output {
+    /// Used as code, for method!
+    Tokens,
+    /// A string literal, for method_str!
+    Str,
+    /// Items, for method_items!
+    Items,
+    /// The exit code as an integer literal, for method_status!
+    Status,
+    /// Nothing, for static_run_assert!
+    Nothing
+}
+impl Output {
+    fn macro_name(&self) -> &'static str {
+        match self {
+            Output::Tokens => "method!",
+            Output::Str => "method_str!",
+            Output::Items => "method_items!",
+            Output::Status => "method_status!",
+            Output::Nothing => "static_run_assert!"
+        }
+    }
+}
+/// Does everything for the method! family,
+/// the only difference being what is done with the output
+fn expand(attr: TokenStream, kind: Output) -> TokenStream {
+    let usage = format!("{0} is used like {0}(file_name, code)", kind.macro_name());
+    // Where items go needs a ; after the compile_error!
+    let fail = |mut error: TokenStream| {
+        if matches!(kind, Output::Items | Output::Nothing) {
+            error.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+        }
+        error
+    };
+    let mut call = match parse_call(attr, &usage) {
+        Ok(call) => call,
+        Err(error) => return fail(error)
+    };
+    call.options.status = matches!(kind, Output::Status);
+    if call.options.encode.is_some() && !matches!(kind, Output::Str) {
+        return fail(compile_error("encode only works with method_str!, since what it gives isn't code", Span::call_site()))
+    }
+    let items = match kind {
+        Output::Tokens => guess_items(&call.code),
+        Output::Items | Output::Nothing => true,
+        Output::Str | Output::Status => false
+    };
+    if let Some(stub) = stub(std::env::var("ABES_PROCS_SKIP").ok(), &kind, items) {
+        return stub.parse().unwrap()
+    }
+    match run_cached(&call) {
+        Ok(output) => {
+            let (output, items) = match kind {
+                Output::Tokens => {
+                    // Printing nothing works either way, but what tracks it doesn't,
+                    // so it goes off the same guess as skipping does
+                    let items = match output.trim().is_empty() {
+                        true => items,
+                        false => is_items(&output)
+                    };
+                    (output, items)
+                }
+                Output::Str => (Literal::string(&output).to_string(), false),
+                Output::Items => (output, true),
+                Output::Status => (output, false),
+                // Being nothing works where items go and where statements go
+                Output::Nothing => (String::new(), true)
+            };
+            track(&call.tracked, &output, items)
+                .parse::<TokenStream>()
+                .unwrap()
+        }
+        Err(error) => fail(compile_error(&error, Span::call_site()))
+    }
+}
+/// What a call becomes instead when ABES_PROCS_SKIP is on, where items go if `items`,
+/// which is None when it should be run like normal
+fn stub(skip: Option<String>, kind: &Output, items: bool) -> Option<String> {
+    if !flag(skip) {
+        return None
+    }
+    let tracked = tracked_env();
+    if items {
+        return Some(tracked)
+    }
+    let value = match kind {
+        Output::Str => "\"\"",
+        Output::Status => "0",
+        _ => "Default::default()"
+    };
+    Some(format!("{{ {tracked} {value} }}"))
+}
+/// Guesses if code prints items from the first string in it,
+/// which is usually what gets printed first
+fn guess_items(code: &str) -> bool {
+    fn first_string(tokens: TokenStream) -> Option<String> {
+        tokens.into_iter().find_map(|token| match token {
+            TokenTree::Literal(literal) => string_literal(&literal),
+            TokenTree::Group(group) => first_string(group.stream()),
+            _ => None
+        })
+    }
+    // The code is known to parse, it came from tokens or was checked when it was read
+    match code.parse::<TokenStream>().ok().and_then(first_string) {
+        Some(first) => starts_items(&first),
+        None => false
+    }
+}
+/// Everything given to a method! call
+struct Call {
+    path: String,
+    code: String,
+    options: Options,
+    /// The files the macro has to be expanded again for when they change
+    tracked: Vec<PathBuf>
+}
+/// Reads the arguments of a method! call,
+/// giving back the error for where it went wrong if it couldn't
+fn parse_call(attr: TokenStream, usage: &str) -> Result<Call, TokenStream> {
     // Getting path
     let mut trees = attr.into_iter();
-    let path = if let TokenTree::Ident(ident) = trees.next().unwrap() {
-        ident.to_string()
-    } else {
-        panic!("could not get path")
+    let (path, span) = match trees.next() {
+        Some(TokenTree::Ident(ident)) => {
+            let path = ident.to_string();
+            (path.strip_prefix("r#").map(str::to_string).unwrap_or(path), ident.span())
+        }
+        Some(TokenTree::Literal(literal)) => match string_literal(&literal) {
+            Some(path) => (path, literal.span()),
+            None => return Err(compile_error(&format!("expected a file name. {usage}"), literal.span()))
+        },
+        Some(other) => return Err(compile_error(&format!("expected a file name. {usage}"), other.span())),
+        None => return Err(compile_error(&format!("missing the file name and code. {usage}"), Span::call_site()))
     };
+    // It gets used for the file names(and the crate name with cargo-driver),
+    // so anything that would put them somewhere else isn't allowed
+    if path.is_empty() || !path.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(compile_error(
+            &format!("`{path}` can't be used as a file name, it can only have letters, numbers, _, and -"),
+            span
+        ))
+    }
 
     // Checking format
     if !matches!(trees.next(), Some(TokenTree::Punct(p)) if p.as_char() == ',') {
-        panic!("expected comma after filename");
+        return Err(compile_error(&format!("expected a comma after the file name. {usage}"), span))
+    }
+
+    // Getting params, which are `{ NAME = value, ... },` right after the file name.
+    // Code can't be a block followed by a comma, so that can't be mistaken for it
+    let rest = trees.collect::<Vec<TokenTree>>();
+    let mut rest = rest.as_slice();
+    let mut params = String::new();
+    if let [TokenTree::Group(group), TokenTree::Punct(comma), after @ ..] = rest {
+        if group.delimiter() == Delimiter::Brace && comma.as_char() == ',' {
+            params = parse_params(group)?;
+            rest = after;
+        }
+    }
+
+    // Getting options, which are `key = value,` before the code
+    let mut options = Options::from_env();
+    let mut include = None;
+    let mut deps = Vec::new();
+    while let [TokenTree::Ident(key), TokenTree::Punct(eq), value, after @ ..] = rest {
+        if eq.as_char() != '=' {
+            break
+        }
+        match key.to_string().as_str() {
+            "include" => include = Some(string_option(key, value)?),
+            "toolchain" => options.toolchain = Some(string_option(key, value)?),
+            "cache_key" => options.cache_key = Some(string_option(key, value)?),
+            "build_dir" => options.build_dir = Some(build_dir(
+                &string_option(key, value)?,
+                &std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set"),
+                std::env::var_os("CARGO_TARGET_DIR").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+            )),
+            "rustflags" => options.rustflags = match string_option(key, value)?.as_str() {
+                "inherit" => rustflags(
+                    std::env::var("CARGO_ENCODED_RUSTFLAGS").ok(),
+                    std::env::var("RUSTFLAGS").ok()
+                ),
+                "ignore" => Vec::new(),
+                other => return Err(compile_error(
+                    &format!("unknown rustflags: {other}, it can be inherit or ignore"),
+                    value.span()
+                ))
+            },
+            "fmt" => options.fmt = match value.to_string().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => return Err(compile_error("expected true or false for fmt", value.span()))
+            },
+            "trim" => options.keep_newline = match value.to_string().as_str() {
+                "true" => false,
+                "false" => true,
+                _ => return Err(compile_error("expected true or false for trim", value.span()))
+            },
+            "encode" => options.encode = Some(match string_option(key, value)?.as_str() {
+                "hex" => Encoding::Hex,
+                "base64" => Encoding::Base64,
+                other => return Err(compile_error(
+                    &format!("unknown encoding: {other}, it can be hex or base64"),
+                    value.span()
+                ))
+            }),
+            "timeout_ms" => {
+                let timeout = match value {
+                    TokenTree::Literal(literal) => literal.to_string().parse::<u64>().ok(),
+                    _ => None
+                }.ok_or_else(|| compile_error("expected a number of milliseconds for timeout_ms", value.span()))?;
+                options.timeout = Some(Duration::from_millis(timeout));
+            }
+            "deps" => deps.extend(string_list(key, value, "files", "a.txt")?),
+            "env_clear" => match value.to_string().as_str() {
+                "true" => _ = options.env_allow.get_or_insert_with(Vec::new),
+                "false" => options.env_allow = None,
+                _ => return Err(compile_error("expected true or false for env_clear", value.span()))
+            },
+            "env_allow" => options.env_allow.get_or_insert_with(Vec::new)
+                .extend(string_list(key, value, "environment variables", "PATH")?),
+            other => return Err(compile_error(&format!("unknown option: {other}"), key.span()))
+        }
+        rest = match after {
+            [TokenTree::Punct(comma), after @ ..] if comma.as_char() == ',' => after,
+            _ => after
+        };
     }
 
     // Getting code
-    let code = trees.collect::<TokenStream>().to_string();
+    let manifest_dir = || std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let mut tracked = deps.into_iter()
+        .map(|dep| Path::new(&manifest_dir()).join(dep))
+        .collect::<Vec<PathBuf>>();
+    let code = match include {
+        Some(include) => {
+            if let Some(first) = rest.first() {
+                return Err(compile_error("code can't be given when using include", first.span()))
+            }
+            let include = Path::new(&manifest_dir()).join(include);
+            let code = std::fs::read_to_string(&include).map_err(|error| compile_error(
+                &format!("failed to read {}: {error}", include.display()),
+                Span::call_site()
+            ))?;
+            tracked.push(include);
+            code
+        }
+        None => {
+            let code = rest.iter().cloned().collect::<TokenStream>().to_string();
+            // Without a main, the code is treated as the inside of one
+            let has_main = rest.windows(2).any(|pair| matches!(
+                pair,
+                [TokenTree::Ident(keyword), TokenTree::Ident(name)]
+                    if keyword.to_string() == "fn" && name.to_string() == "main"
+            ));
+            if has_main {
+                code
+            }
+            else {
+                // Inner attributes like #![allow(...)] are for the whole file,
+                // so they stay in front instead of going in main
+                let mut inner = 0;
+                while let [TokenTree::Punct(hash), TokenTree::Punct(bang), TokenTree::Group(group), ..] = &rest[inner..] {
+                    if hash.as_char() != '#' || bang.as_char() != '!' || group.delimiter() != Delimiter::Bracket {
+                        break
+                    }
+                    inner += 3;
+                }
+                let (attributes, body) = rest.split_at(inner);
+                format!(
+                    "{} fn main() {{ {} }}",
+                    attributes.iter().cloned().collect::<TokenStream>(),
+                    body.iter().cloned().collect::<TokenStream>()
+                )
+            }
+        }
+    };
+    // At the end so that inner attributes like #![allow(...)] can still be first
+    let code = code + &params;
 
+    Ok(Call {
+        path,
+        code,
+        options,
+        tracked
+    })
+}
+/// Turns `{ NAME = value, NAME: Type = value }` into a const for each one
+fn parse_params(group: &Group) -> Result<String, TokenStream> {
+    let tokens = group.stream().into_iter().collect::<Vec<TokenTree>>();
+    let mut out = String::new();
+    for param in split_top_level(&tokens, ',') {
+        let (name, rest) = match param {
+            [] => continue,
+            [TokenTree::Ident(name), rest @ ..] => (name, rest),
+            [other, ..] => return Err(compile_error("expected a param like NAME = value", other.span()))
+        };
+        let Some(eq) = rest.iter().position(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == '=')) else {
+            return Err(compile_error(&format!("expected {name} = value"), name.span()))
+        };
+        let value = rest[eq + 1..].iter().cloned().collect::<TokenStream>().to_string();
+        let data_type = match &rest[..eq] {
+            [] => param_type(&value).ok_or_else(|| compile_error(
+                &format!("the type of {name} can't be guessed, give it like {name}: Type = value"),
+                name.span()
+            ))?,
+            [TokenTree::Punct(colon), data_type @ ..] if colon.as_char() == ':' && !data_type.is_empty() => {
+                data_type.iter().cloned().collect::<TokenStream>().to_string()
+            }
+            _ => return Err(compile_error(&format!("expected {name}: Type = value"), name.span()))
+        };
+        if value.is_empty() {
+            return Err(compile_error(&format!("missing the value of {name}"), name.span()))
+        }
+        out += &format!("\n#[allow(dead_code)] const {name}: {data_type} = {value};");
+    }
+    Ok(out)
+}
+/// The type of a param with just a value, for the ones it is obvious for.
+/// Numbers are i64 or f64 unless they have a suffix like 5u8
+fn param_type(value: &str) -> Option<String> {
+    let number = value.strip_prefix('-').unwrap_or(value).trim_start();
+    if value.starts_with('"') || value.starts_with("r\"") || value.starts_with("r#") {
+        Some("&str".to_string())
+    }
+    else if value.starts_with('\'') {
+        Some("char".to_string())
+    }
+    else if value == "true" || value == "false" {
+        Some("bool".to_string())
+    }
+    else if number.starts_with(|c: char| c.is_ascii_digit()) {
+        let suffix = [
+            "u8", "u16", "u32", "u64", "u128", "usize",
+            "i8", "i16", "i32", "i64", "i128", "isize", "f32", "f64"
+        ].into_iter().find(|suffix| number.ends_with(suffix));
+        let hex = number.starts_with("0x") || number.starts_with("0b") || number.starts_with("0o");
+        // Hex can end in f64 without being a float
+        let suffix = suffix.filter(|suffix| !(hex && suffix.starts_with('f')));
+        Some(match suffix {
+            Some(suffix) => suffix.to_string(),
+            // e can be a digit in hex
+            None if !hex && number.contains(['.', 'e', 'E']) => "f64".to_string(),
+            None => "i64".to_string()
+        })
+    }
+    else {
+        None
+    }
+}
+/// Gets the string out of an option like `include = "file.rs"`
+fn string_option(key: &Ident, value: &TokenTree) -> Result<String, TokenStream> {
+    match value {
+        TokenTree::Literal(literal) => string_literal(literal),
+        _ => None
+    }.ok_or_else(|| compile_error(&format!("expected a string for {key}"), value.span()))
+}
+/// Gets the contents of a string literal,
+/// or None if it isn't one
+fn string_literal(literal: &Literal) -> Option<String> {
+    let text = literal.to_string();
+    if let Some(raw) = text.strip_prefix('r') {
+        // Raw strings have no escapes, just the hashes to remove
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let inner = raw[hashes..]
+            .strip_prefix('"')?
+            .strip_suffix(&format!("\"{}", "#".repeat(hashes)))?;
+        return Some(inner.to_string())
+    }
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            '\\' => out.push('\\'),
+            '\'' => out.push('\''),
+            '"' => out.push('"'),
+            'x' => {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                out.push(u8::from_str_radix(&hex, 16).ok()? as char);
+            }
+            'u' => {
+                let hex = chars.by_ref().skip(1).take_while(|c| *c != '}').collect::<String>();
+                out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+            // Line continuation, which skips the newline and leading whitespace
+            '\n' => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+            _ => return None
+        }
+    }
+    Some(out)
+}
+/// The environment variables that change what the method! family does
+const TRACKED_ENV: &[&str] = &[
+    "ABES_PROCS_SKIP", "ABES_PROCS_KEEP", "ABES_PROCS_BACKTRACE", "ABES_PROCS_RECORD_DIR", "ABES_PROCS_JOBS"
+];
+/// Uses each of [TRACKED_ENV] with option_env!,
+/// which is what makes cargo build it again when one of them changes
+fn tracked_env() -> String {
+    TRACKED_ENV.iter()
+        .map(|name| format!("const _: Option<&str> = option_env!({name:?});"))
+        .collect()
+}
+/// Makes the output depend on the files and [TRACKED_ENV] so that
+/// cargo knows to expand the macro again when they change.
+/// This is done through include_bytes and option_env!, which need a const
+/// to go in, so expressions get put in a block with it.
+fn track(files: &[std::path::PathBuf], output: &str, items: bool) -> String {
+    let includes = files.iter()
+        .map(|file| format!("const _: &[u8] = include_bytes!({});", Literal::string(&file.to_string_lossy())))
+        .collect::<String>() + &tracked_env();
+    if items {
+        includes + output
+    }
+    else {
+        format!("{{{includes}{output}}}")
+    }
+}
+/// What items can start with
+const ITEM_KEYWORDS: &[&str] = &[
+    "fn", "struct", "enum", "union", "impl", "trait", "type", "mod", "use",
+    "const", "static", "extern", "pub", "macro_rules", "unsafe", "async"
+];
+/// Guesses if the output is items or an expression from how it starts
+fn is_items(output: &str) -> bool {
+    let output_tokens = output.parse::<TokenStream>().unwrap();
+    match output_tokens.into_iter().next() {
+        None => true,
+        Some(TokenTree::Punct(punct)) => punct.as_char() == '#',
+        Some(TokenTree::Ident(ident)) => ITEM_KEYWORDS.contains(&ident.to_string().as_str()),
+        _ => false
+    }
+}
+/// The same as [is_items], but for only the start of the output,
+/// which might not be tokens on its own(like `"fn a() {"`)
+fn starts_items(start: &str) -> bool {
+    let start = start.trim_start();
+    let word = start.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or("");
+    start.is_empty() || start.starts_with('#') || ITEM_KEYWORDS.contains(&word)
+}
+/// What the calls with a cache_key got, which lasts as long as the compiler has the macro loaded
+type Cache = std::collections::HashMap<String, std::sync::Arc<std::sync::OnceLock<(String, Result<String, String>)>>>;
+static CACHE: std::sync::LazyLock<std::sync::Mutex<Cache>> = std::sync::LazyLock::new(Default::default);
+/// The same as [run], but only the first call for a cache_key runs
+/// and the rest get what it printed
+fn run_cached(call: &Call) -> Result<String, String> {
+    let Some(key) = &call.options.cache_key else {
+        return run(&call.path, &call.code, &call.options)
+    };
+    let entry = CACHE.lock()
+        .unwrap()
+        .entry(key.clone())
+        .or_default()
+        .clone();
+    // Anything else with the key waits here until the first one is done,
+    // without stopping the calls for other keys
+    let (code, output) = entry.get_or_init(|| (call.code.clone(), run(&call.path, &call.code, &call.options)));
+    if code != &call.code {
+        return Err(format!("cache_key {key} is already used for different code"))
+    }
+    output.clone()
+}
+/// Compiles and runs the code, returning what it printed to stdout
+/// or why it couldn't.
+/// All the files made along the way get deleted, even if it panics.
+fn run(path: &str, code: &str, options: &Options) -> Result<String, String> {
+    let output = compile_and_run(path, code, options)?;
+    if let Some(dir) = &options.record_dir {
+        record(dir, path, code, &output)?;
+    }
+    Ok(output)
+}
+/// Saves the code as (name).rs and what it printed as (name).out in the directory
+fn record(dir: &Path, path: &str, code: &str, output: &str) -> Result<(), String> {
+    let write = |name: String, contents: &str| {
+        let file = dir.join(name);
+        std::fs::write(&file, contents)
+            .map_err(|error| format!("failed to record {}: {error}", file.display()))
+    };
+    std::fs::create_dir_all(dir)
+        .map_err(|error| format!("failed to make ABES_PROCS_RECORD_DIR {}: {error}", dir.display()))?;
+    write(format!("{path}.rs"), code)?;
+    write(format!("{path}.out"), output)
+}
+/// Does all of the work for [run]
+fn compile_and_run(path: &str, code: &str, options: &Options) -> Result<String, String> {
     // Getting edition
-    let cargo_toml_content = std::fs::read_to_string("Cargo.toml").expect("failed to load Cargo.toml");
-    let manifest = toml::from_str::<CargoManifest>(&cargo_toml_content).expect("failed to parse Cargo.toml");
-    let edition = &manifest.package.edition;
+    let cargo_toml_content = std::fs::read_to_string("Cargo.toml")
+        .map_err(|error| format!("failed to load Cargo.toml: {error}"))?;
+    let edition = &edition(&cargo_toml_content)?;
+
+    if let Some(toolchain) = &options.toolchain {
+        check_toolchain(toolchain)?;
+    }
+    // The binary always runs here, so it has to be built for here,
+    // even when the crate is being cross compiled
+    let host = host_triple(options)?;
+    // Every file the compiler could make has to be tracked before it runs,
+    // otherwise a failed compile or a panic would leave them behind
+    let mut guards = Vec::new();
+    let bin_path = match options.driver {
+        Driver::Rustc => compile_rustc(path, code, edition, &host, options, &mut guards)?,
+        Driver::Cargo => compile_cargo(path, code, edition, &host, options, &mut guards)?
+    };
+
+    let output = run_binary(&bin_path, options)?;
+    if options.status {
+        // Being stopped by a signal(like from a crash) doesn't give a code
+        return match output.status.code() {
+            Some(code) => Ok(code.to_string()),
+            None => Err(format!("failed to run file: {}", output.status))
+        }
+    }
+    if !output.status.success() {
+        // What it printed to stderr is where the panic message is
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim_end();
+        if stderr.is_empty() {
+            return Err(format!("failed to run file: {}", output.status))
+        }
+        return Err(format!("failed to run file: {}\n{stderr}", output.status))
+    }
+    if let Some(encoding) = options.encode {
+        return Ok(encoding.encode(&output.stdout))
+    }
 
-    let rs_path = format!("{path}.rs");
-    std::fs::write(&rs_path, code).expect("failed to make file");
-    let _rs_path_guard = DeleteOnDrop::new(&rs_path);
+    let output = String::from_utf8(output.stdout).map_err(|error| {
+        // Showing the bytes around where it went wrong
+        // makes it a lot easier to find what printed them
+        let bad = error.utf8_error().valid_up_to();
+        let bytes = error.as_bytes();
+        let start = bad.saturating_sub(16);
+        let end = (bad + 16).min(bytes.len());
+        format!(
+            "the code printed something that isn't utf8, \
+            but what it prints is used as rust so it has to be. \
+            {error}, near b\"{}\"",
+            bytes[start..end].escape_ascii()
+        )
+    })?;
+    // println! is easy to use by accident, so the newline from it is taken off
+    let output = match options.keep_newline {
+        true => output,
+        false => match output.strip_suffix('\n') {
+            Some(trimmed) => trimmed.strip_suffix('\r').unwrap_or(trimmed).to_string(),
+            None => output
+        }
+    };
+    if options.fmt {
+        // Not being able to format it shouldn't stop it from being used
+        return Ok(format_output(&output, edition).unwrap_or_else(|error| {
+            eprintln!("note: didn't format the output of {path}: {error}");
+            output
+        }))
+    }
+    Ok(output)
+}
+/// Runs the output through rustfmt,
+/// which only works if it is items (not an expression)
+fn format_output(output: &str, edition: &str) -> Result<String, String> {
+    use std::io::Write;
+    let mut child = std::process::Command::new("rustfmt")
+        .arg("--edition")
+        .arg(edition)
+        .arg("--emit")
+        .arg("stdout")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => "rustfmt could not be found".to_string(),
+            _ => format!("failed to run rustfmt: {error}")
+        })?;
+    // Dropping stdin right after closes it so rustfmt knows that is everything
+    child.stdin.take().unwrap().write_all(output.as_bytes())
+        .map_err(|error| format!("failed to give the output to rustfmt: {error}"))?;
+    let formatted = child.wait_with_output().map_err(|error| format!("failed to run rustfmt: {error}"))?;
+    if !formatted.status.success() {
+        let stderr = String::from_utf8_lossy(&formatted.stderr);
+        return Err(format!("rustfmt failed ({})", stderr.lines().next().unwrap_or_default().trim()))
+    }
+    String::from_utf8(formatted.stdout).map_err(|error| format!("rustfmt gave back invalid utf8: {error}"))
+}
+/// Gets the edition out of the Cargo.toml,
+/// making sure it is one rustc knows about first
+/// so that there is a better error than rustc would give
+fn edition(cargo_toml: &str) -> Result<String, String> {
+    const EDITIONS: [&str; 4] = ["2015", "2018", "2021", "2024"];
+    let manifest = toml::from_str::<CargoManifest>(cargo_toml)
+        .map_err(|error| format!("failed to parse Cargo.toml: {error}"))?;
+    let edition = manifest.package.edition;
+    if !EDITIONS.contains(&edition.as_str()) {
+        return Err(format!(
+            "method! doesn't know about edition \"{edition}\" from Cargo.toml, it has to be one of {}",
+            EDITIONS.join(", ")
+        ))
+    }
+    Ok(edition)
+}
+/// Builds the file with just rustc, giving back where the binary is
+fn compile_rustc(
+    path: &str,
+    code: &str,
+    edition: &str,
+    host: &str,
+    options: &Options,
+    guards: &mut Vec<DeleteOnDrop<PathBuf>>
+) -> Result<PathBuf, String> {
+    // In a build_dir, the files are kept around to be used again
+    let (dir, path) = match &options.build_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .map_err(|error| format!("failed to make {}: {error}", dir.display()))?;
+            (dir.clone(), build_name(path, code, edition, host, options))
+        }
+        None => (PathBuf::from("."), path.to_string())
+    };
+    let rs_path = dir.join(format!("{path}.rs"));
+    // Have to do this for windows compatability
+    let bin_path = dir.join(format!("{path}{}", std::env::consts::EXE_SUFFIX));
+    if options.build_dir.is_some() {
+        if bin_path.exists() {
+            return Ok(bin_path)
+        }
+    }
+    else {
+        guards.push(DeleteOnDrop::new(rs_path.clone(), options.keep));
+        guards.push(DeleteOnDrop::new(bin_path.clone(), options.keep));
+        guards.push(DeleteOnDrop::new(dir.join(format!("{path}.pdb")), options.keep));// msvc debug info
+    }
+    std::fs::write(&rs_path, code).map_err(|error| format!("failed to make file: {error}"))?;
 
-    let compile_status = std::process::Command::new("rustc")
+    let compile_status = options.rustc_command()
         .arg(&rs_path)
         .arg("--edition")
         .arg(edition)
+        .arg("--target")
+        .arg(host)
+        .arg("-o")
+        .arg(&bin_path)
+        .args(&options.rustflags)
         .spawn()// Allows getting input from the terminal
         .and_then(|mut c| c.wait())
-        .expect("failed to compile");
+        .map_err(|error| rustc_error(options.rustc_name(), "failed to compile", error))?;
     if !compile_status.success() {
-        panic!("failed to compile: {compile_status}")
+        return Err(format!("failed to compile: {compile_status}"))
     }
-
-    let bin_path = Path::new(".").join(&path);// Have to do this for windows compatability
-    let _bin_path_guard = DeleteOnDrop::new(&bin_path);
-    // This section has to be before we run the command becasue running it could fail
-    // and the bin would be left undeleted
-
-    let output = std::process::Command::new(&bin_path).output().expect("failed to run file");
-    if !output.status.success() {
-        panic!("failed to run file: {}", output.status);
+    Ok(bin_path)
+}
+/// Reads an option that is a list of strings, like `deps = ["a.txt"]`,
+/// where `what` and `example` are for the error if it isn't
+fn string_list(key: &Ident, value: &TokenTree, what: &str, example: &str) -> Result<Vec<String>, TokenStream> {
+    let TokenTree::Group(group) = value else {
+        return Err(compile_error(&format!("expected a list of {what} for {key}, like [\"{example}\"]"), value.span()))
+    };
+    if group.delimiter() != Delimiter::Bracket {
+        return Err(compile_error(&format!("expected a list of {what} for {key}, like [\"{example}\"]"), value.span()))
+    }
+    let items = group.stream().into_iter().collect::<Vec<TokenTree>>();
+    let mut out = Vec::new();
+    for item in split_top_level(&items, ',') {
+        match item {
+            [] => {}
+            [item] => out.push(string_option(key, item)?),
+            _ => return Err(compile_error(&format!("expected a string for each of {key}"), group.span()))
+        }
     }
+    Ok(out)
+}
+/// If an environment variable like ABES_PROCS_KEEP is turned on,
+/// which is anything other than nothing or 0
+fn flag(value: Option<String>) -> bool {
+    value.is_some_and(|value| !value.is_empty() && value != "0")
+}
+/// Where `build_dir = "..."` is, which is relative to the crate root,
+/// except that `target` is CARGO_TARGET_DIR when that is set
+fn build_dir(dir: &str, manifest_dir: &str, target_dir: Option<PathBuf>) -> PathBuf {
+    let dir = Path::new(dir);
+    match (dir.strip_prefix("target"), target_dir) {
+        (Ok(rest), Some(target_dir)) => target_dir.join(rest),
+        _ => Path::new(manifest_dir).join(dir)
+    }
+}
+/// The name of what gets built in a build_dir,
+/// which is different whenever anything that changes what gets built does,
+/// so using the same one again is only done when it would be the same
+fn build_name(path: &str, code: &str, edition: &str, host: &str, options: &Options) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (code, edition, host, &options.rustc, &options.toolchain, &options.rustflags).hash(&mut hasher);
+    format!("{path}-{:016x}", hasher.finish())
+}
+/// The flags to build with from CARGO_ENCODED_RUSTFLAGS and RUSTFLAGS,
+/// with the encoded ones being used first, the same as cargo does
+fn rustflags(encoded: Option<String>, plain: Option<String>) -> Vec<String> {
+    match (encoded, plain) {
+        (Some(encoded), _) if !encoded.is_empty() => encoded.split('\x1f').map(str::to_string).collect(),
+        (_, Some(plain)) => plain.split_whitespace().map(str::to_string).collect(),
+        _ => Vec::new()
+    }
+}
+/// Builds the file as the main.rs of a crate in the (name) folder,
+/// giving back where the binary is.
+/// It is built with cargo build instead of cargo run
+/// so that it gets run the same way as with rustc.
+fn compile_cargo(
+    path: &str,
+    code: &str,
+    edition: &str,
+    host: &str,
+    options: &Options,
+    guards: &mut Vec<DeleteOnDrop<PathBuf>>
+) -> Result<PathBuf, String> {
+    // Cargo already only builds again when something changed,
+    // so in a build_dir the crate just gets kept
+    let crate_path = match &options.build_dir {
+        Some(dir) => dir.join(path),
+        None => {
+            guards.push(DeleteOnDrop::new(PathBuf::from(path), options.keep));
+            PathBuf::from(path)
+        }
+    };
+    std::fs::create_dir_all(crate_path.join("src")).map_err(|error| format!("failed to make crate: {error}"))?;
+    // The [workspace] stops cargo from thinking it is part of this crate's workspace
+    let manifest = format!("[package]\nname = \"{path}\"\nversion = \"0.0.0\"\nedition = \"{edition}\"\n\n[workspace]\n");
+    std::fs::write(crate_path.join("Cargo.toml"), manifest).map_err(|error| format!("failed to make crate: {error}"))?;
+    std::fs::write(crate_path.join("src").join("main.rs"), code).map_err(|error| format!("failed to make file: {error}"))?;
 
-    String::from_utf8(output.stdout)
-        .unwrap()
-        .parse::<TokenStream>()
-        .unwrap()
+    let target_dir = crate_path.join("target");
+    let mut command = options.cargo_command();
+    command
+        .arg("build")
+        .arg("--quiet")
+        .arg("--offline")
+        .arg("--manifest-path")
+        .arg(crate_path.join("Cargo.toml"))
+        .arg("--target")
+        .arg(host)
+        .arg("--target-dir")
+        .arg(&target_dir);
+    if let (Some(rustc), None) = (&options.rustc, &options.toolchain) {
+        command.env("RUSTC", rustc);
+    }
+    // Cargo would use the RUSTFLAGS it was given otherwise,
+    // which should only happen with `rustflags = "inherit"`
+    command.env_remove("RUSTFLAGS");
+    command.env_remove("CARGO_ENCODED_RUSTFLAGS");
+    // Under `cargo clippy` these would run clippy on the code too,
+    // which isn't the crate being checked
+    command.env_remove("RUSTC_WRAPPER");
+    command.env_remove("RUSTC_WORKSPACE_WRAPPER");
+    if !options.rustflags.is_empty() {
+        command.env("CARGO_ENCODED_RUSTFLAGS", options.rustflags.join("\x1f"));
+    }
+    let compile_status = command
+        .spawn()// Allows getting input from the terminal
+        .and_then(|mut c| c.wait())
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => format!(
+                "the cargo-driver feature needs cargo, but `{}` could not be found",
+                command.get_program().to_string_lossy()
+            ),
+            _ => format!("failed to compile: {error}")
+        })?;
+    if !compile_status.success() {
+        return Err(format!("failed to compile: {compile_status}"))
+    }
+    Ok(target_dir.join(host).join("debug").join(format!("{path}{}", std::env::consts::EXE_SUFFIX)))
+}
+/// Runs the binary and gets what it printed,
+/// stopping it if it runs for longer than the timeout
+fn run_binary(bin_path: &Path, options: &Options) -> Result<std::process::Output, String> {
+    let mut command = std::process::Command::new(bin_path);
+    if let Some(allowed) = &options.env_allow {
+        command.env_clear();
+        for name in allowed {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+    }
+    if options.backtrace {
+        command.env("RUST_BACKTRACE", "1");
+    }
+    let mut child = command
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("failed to run file: {error}"))?;
+    // The output has to be read while it runs,
+    // otherwise it could fill up the pipe and never finish.
+    // stderr is for logging, so it goes to the build output as it comes in too
+    fn read_all(mut pipe: impl std::io::Read + Send + 'static, echo: bool) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut out = Vec::new();
+            let mut buffer = [0; 4096];
+            while let Ok(read @ 1..) = pipe.read(&mut buffer) {
+                if echo {
+                    _ = std::io::Write::write_all(&mut std::io::stderr(), &buffer[..read]);
+                }
+                out.extend_from_slice(&buffer[..read]);
+            }
+            out
+        })
+    }
+    let stdout = read_all(child.stdout.take().unwrap(), false);
+    let stderr = read_all(child.stderr.take().unwrap(), true);
+    let status = match options.timeout {
+        None => child.wait().map_err(|error| format!("failed to run file: {error}"))?,
+        Some(timeout) => {
+            let deadline = Instant::now() + timeout;
+            loop {
+                if let Some(status) = child.try_wait().map_err(|error| format!("failed to run file: {error}"))? {
+                    break status
+                }
+                if Instant::now() >= deadline {
+                    _ = child.kill();
+                    _ = child.wait();
+                    return Err(format!(
+                        "the code ran for longer than its timeout of {}ms, so it was stopped",
+                        timeout.as_millis()
+                    ))
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap(),
+        stderr: stderr.join().unwrap()
+    })
+}
+/// Gets the target triple of the machine doing the compiling,
+/// from HOST if it is set, otherwise by asking rustc
+fn host_triple(options: &Options) -> Result<String, String> {
+    if let Ok(host) = std::env::var("HOST") {
+        return Ok(host)
+    }
+    let rustc = options.rustc_name();
+    let output = options.rustc_command()
+        .arg("-vV")
+        .output()
+        .map_err(|error| rustc_error(rustc, "failed to get host", error))?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_string())
+        .ok_or_else(|| format!("failed to get host from `{rustc} -vV`"))
+}
+/// Makes sure the toolchain is there before using it,
+/// since otherwise rustup's error gets lost in the rest of the build output
+fn check_toolchain(toolchain: &str) -> Result<(), String> {
+    let output = rustup_run(toolchain, "rustc")
+        .arg("-V")
+        .output()
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => format!("toolchain = \"{toolchain}\" needs rustup, but it could not be found"),
+            _ => format!("failed to run rustup: {error}")
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "the {toolchain} toolchain couldn't be used, it might need to be installed with \
+            `rustup toolchain install {toolchain}` ({})",
+            stderr.lines().next().unwrap_or_default().trim()
+        ))
+    }
+    Ok(())
+}
+/// Explains why rustc couldn't be run,
+/// which is usually because it isn't installed(or on the PATH)
+fn rustc_error(rustc: &str, context: &str, error: std::io::Error) -> String {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => format!(
+            "method! needs rustc to compile the code, but `{rustc}` could not be found \
+            (a different one can be used by setting the RUSTC environment variable)"
+        ),
+        _ => format!("{context}: {error}")
+    }
+}
+/// Makes an error that points at the span,
+/// which is the macro call for Span::call_site()
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut group = Group::new(Delimiter::Parenthesis, TokenTree::Literal(message).into());
+    group.set_span(span);
+    [
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(group)
+    ].into_iter().collect()
 }
 enum What {
     Struct,
@@ -196,166 +1610,1173 @@ struct DeriveData {
     what: What,
     name: Ident,
     generic: Vec<TokenTree>,
-    fields: Vec<Field>
+    /// What is after `where`, without it
+    where_clause: Vec<TokenTree>,
+    fields: Vec<Field>,
+    variants: Vec<Variant>,
+    attributes: Attributes
+}
+/// The options given through `#[binary(...)]` on the type
+#[derive(Default)]
+struct Attributes {
+    /// Writes the number of fields first, and the length of each field,
+    /// so that versions with more or less fields can still read it
+    tagged: bool,
+    /// Writes the length of everything first,
+    /// so that it can be skipped without knowing what it is
+    framed: bool,
+    /// When the data runs out before a field starts,
+    /// that field and the rest get defaulted instead of panicking,
+    /// so shorter records from older versions can still be read
+    default_on_eof: bool,
+    /// The same as default_on_eof, except that the fields that aren't there
+    /// come from the Default of the whole struct instead of each field's
+    default_rest: bool,
+    /// Writes a CRC32 of everything after it, which gets checked when reading
+    checksum: bool,
+    /// Run length encodes everything, writing the length of that first,
+    /// for records that are mostly the same byte over and over
+    compress: bool,
+    /// The unit variant an enum reads as when the variant isn't known,
+    /// which needs framed to be able to skip the unknown one
+    unknown: Option<String>,
+    /// Where the traits come from, for when they aren't in scope(or are something else),
+    /// set by `crate = "path"`
+    krate: Option<String>,
+    /// Also makes from_binary_into read each field into what is already there,
+    /// instead of replacing the whole thing
+    reuse: bool,
+    /// Makes from_binary_slice and BinaryReader::read_binary panic
+    /// when there is anything left over after reading it
+    strict: bool,
+    /// The order the fields are written and read in, instead of the order they are declared in,
+    /// set by `order(c, a, b)`
+    order: Option<Vec<String>>,
+    /// Makes the FromBinary derive implement FromBinaryBorrowed instead,
+    /// so that fields like `&'a [u8]` can borrow from what is being read
+    zero_copy: bool,
+    /// Writes zeros after everything else until the length is a multiple of it,
+    /// which reading skips, set by `align = 8`
+    align: Option<usize>,
+    /// How an enum writes which variant it is
+    discriminant: Discriminant
+}
+impl Attributes {
+    /// Takes the inside of a `#[...]`, ignoring it if it isn't `binary(...)`
+    fn parse(&mut self, attribute: TokenStream) -> Result<(), String> {
+        let mut iter = attribute.into_iter();
+        if !matches!(iter.next(), Some(TokenTree::Ident(ident)) if ident.to_string() == "binary") {
+            return Ok(())
+        }
+        let options = match iter.next() {
+            Some(TokenTree::Group(group)) => group.stream().into_iter().collect::<Vec<TokenTree>>(),
+            _ => return Err("expected binary(...)".to_string())
+        };
+        for option in options.split(|x| {
+            if let TokenTree::Punct(punct) = x {
+                if punct.to_string() == "," {
+                    return true
+                }
+            }
+            false
+        }) {
+            let Some(name) = option.first() else { continue };
+            // For options like `name = "value"`
+            let value = || match option.get(2) {
+                Some(TokenTree::Literal(literal)) => string_literal(literal),
+                _ => None
+            }.ok_or_else(|| format!("expected {name} = \"...\""));
+            match name.to_string().as_str() {
+                "tagged" => self.tagged = true,
+                "framed" => self.framed = true,
+                "default_on_eof" => self.default_on_eof = true,
+                "default_rest" => self.default_rest = true,
+                "checksum" => self.checksum = true,
+                "compress" => self.compress = true,
+                "crate" => self.krate = Some(value()?),
+                "reuse" => self.reuse = true,
+                "strict" => self.strict = true,
+                "zero_copy" => self.zero_copy = true,
+                "align" => self.align = match option.get(2) {
+                    Some(TokenTree::Literal(literal)) => literal.to_string()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|align| align.is_power_of_two()),
+                    _ => None
+                }.map(Some).ok_or_else(|| "expected align = a power of two, like align = 8".to_string())?,
+                "order" => self.order = match option.get(1) {
+                    Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => Some(
+                        group.stream()
+                            .into_iter()
+                            .filter(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','))
+                            .map(|token| token.to_string())
+                            .collect()
+                    ),
+                    _ => return Err("expected order(field, field, ...)".to_string())
+                },
+                "unknown" => self.unknown = match option.get(2) {
+                    Some(TokenTree::Ident(variant)) => Some(variant.to_string()),
+                    _ => return Err("expected unknown = Variant".to_string())
+                },
+                "discriminant" => self.discriminant = match value()?.as_str() {
+                    "u32" => Discriminant::U32,
+                    "varint" => Discriminant::VarInt,
+                    other => return Err(format!("unknown discriminant: {other}, expected u32 or varint"))
+                },
+                "tag" => self.discriminant = match value()?.as_str() {
+                    "name" => Discriminant::Name,
+                    "index" => Discriminant::U32,
+                    other => return Err(format!("unknown tag: {other}, expected name or index"))
+                },
+                other => return Err(format!("unknown binary option: {other}"))
+            }
+        }
+        Ok(())
+    }
+}
+/// How an enum writes which variant it is
+#[derive(Default)]
+enum Discriminant {
+    #[default]
+    U32,
+    /// As few bytes as it fits in, which is usually one
+    VarInt,
+    /// The name of the variant as a string, set by `tag = "name"`,
+    /// which takes more space but keeps working when variants get moved around
+    Name
+}
+impl Discriminant {
+    fn write(&self, index: usize, name: &str) -> String {
+        match self {
+            Discriminant::U32 => format!("<u32 as __abes_binary::ToBinary>::to_binary(&{index}, write);"),
+            Discriminant::VarInt => format!("<__abes_binary::VarInt as __abes_binary::ToBinary>::to_binary(&__abes_binary::VarInt({index}), write);"),
+            Discriminant::Name => format!("<str as __abes_binary::ToBinary>::to_binary({:?}, write);", variant_name(name))
+        }
+    }
+    fn read(&self) -> &'static str {
+        match self {
+            Discriminant::U32 => "<u32 as __abes_binary::FromBinary>::from_binary(binary)",
+            Discriminant::VarInt => "<__abes_binary::VarInt as __abes_binary::FromBinary>::from_binary(binary).0",
+            Discriminant::Name => "<String as __abes_binary::FromBinary>::from_binary(binary).as_str()"
+        }
+    }
+    /// What [Discriminant::read] gives for the variant
+    fn pattern(&self, index: usize, name: &str) -> String {
+        match self {
+            Discriminant::U32 | Discriminant::VarInt => index.to_string(),
+            Discriminant::Name => format!("{:?}", variant_name(name))
+        }
+    }
+    /// The error for reading a variant that isn't there
+    fn invalid(&self, enum_name: &Ident) -> String {
+        match self {
+            Discriminant::U32 | Discriminant::VarInt => format!("other => panic!(\"invalid discriminant for {enum_name}: {{other}}\")"),
+            Discriminant::Name => format!("other => panic!(\"unknown variant name for {enum_name}: {{other:?}}\")")
+        }
+    }
+    fn size(&self, index: usize, name: &str) -> String {
+        self.fixed_size(index, name).to_string()
+    }
+    /// The same as [Discriminant::size], but as a number
+    fn fixed_size(&self, index: usize, name: &str) -> usize {
+        match self {
+            Discriminant::U32 => 4,
+            // 7 bits fit in each byte
+            Discriminant::VarInt => ((usize::BITS - index.leading_zeros()) as usize).div_ceil(7).max(1),
+            // The length, and then the name
+            Discriminant::Name => 8 + variant_name(name).len()
+        }
+    }
+}
+/// The name of a variant as it is written, without the r# of a raw identifier
+fn variant_name(name: &str) -> &str {
+    name.strip_prefix("r#").unwrap_or(name)
 }
 impl DeriveData {
+    /// Makes the `impl<...> Trait for Name<...>` part,
+    /// requiring every generic type to implement the trait too,
+    /// along with the fields that use associated types of them
+    fn header(&self, trait_name: &str) -> String {
+        self.header_with_bound(trait_name, trait_name)
+    }
+    /// The same as [DeriveData::header], but with a different trait for the generic types
+    fn header_with_bound(&self, trait_name: &str, bound: &str) -> String {
+        let trait_name = &self.path(trait_name);
+        let bound = &self.path(bound);
+        let tokens = |tokens: &[TokenTree]| tokens.iter().cloned().collect::<TokenStream>().to_string();
+        let mut declarations = Vec::new();
+        let mut names = Vec::new();
+        let mut types = Vec::new();
+        let params = match self.generic.as_slice() {
+            [TokenTree::Punct(open), inner @ .., TokenTree::Punct(close)]
+                if open.as_char() == '<' && close.as_char() == '>' => split_top_level(inner, ','),
+            _ => Vec::new()
+        };
+        for param in params {
+            // Defaults are only allowed on the type itself, not the impl
+            let param = split_top_level(param, '=')[0];
+            match param {
+                [] => {}
+                [TokenTree::Punct(quote), lifetime, ..] if quote.as_char() == '\'' => {
+                    declarations.push(tokens(param));
+                    names.push(format!("'{lifetime}"));
+                }
+                [TokenTree::Ident(keyword), name, ..] if keyword.to_string() == "const" => {
+                    declarations.push(tokens(param));
+                    names.push(name.to_string());
+                }
+                [name, bounds @ ..] => {
+                    let separator = if bounds.is_empty() { ":" } else { "+" };
+                    declarations.push(format!("{} {separator} {bound}", tokens(param)));
+                    names.push(name.to_string());
+                    types.push(name.to_string());
+                }
+            }
+        }
+        // Bounding T doesn't bound T::Assoc, so the fields
+        // that use associated types need their own bounds
+        let mut predicates = split_top_level(&self.where_clause, ',')
+            .into_iter()
+            .filter(|predicate| !predicate.is_empty())
+            .map(tokens)
+            .collect::<Vec<String>>();
+        for field in self.fields.iter().chain(self.variants.iter().flat_map(|variant| &variant.fields)) {
+            let field_tokens = field.data_type.parse::<TokenStream>()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<TokenTree>>();
+            let predicate = format!("{}: {bound}", field.data_type);
+            if projects(&field_tokens, &types) && !predicates.contains(&predicate) {
+                predicates.push(predicate);
+            }
+        }
+        let where_clause = match predicates.is_empty() {
+            true => String::new(),
+            false => format!(" where {}", predicates.join(", "))
+        };
+        // The generated code shouldn't be what makes warnings show up
+        let attributes = "#[automatically_derived] #[allow(clippy::all, non_snake_case, unused)]";
+        if names.is_empty() {
+            format!("{attributes} impl {trait_name} for {}", self.name)
+        }
+        else {
+            format!(
+                "{attributes} impl<{}> {trait_name} for {}<{}>{where_clause}",
+                declarations.join(", "),
+                self.name,
+                names.join(", ")
+            )
+        }
+    }
+    /// The path to something from abes_nice_binary, through the name [expand](Self::expand) gives it,
+    /// so that nothing in the module the derive is in can get in the way
+    fn path(&self, item: &str) -> String {
+        format!("__abes_binary::{item}")
+    }
+    /// Makes the impl and gives errors in the code for each field the span of the field.
+    /// It goes in a `const _` so that nothing in it can be seen from outside,
+    /// which still sees everything the module it is in can,
+    /// along with abes_nice_binary as `__abes_binary`, or whatever `crate = "..."` says
+    fn expand(&self, which: Which) -> TokenStream {
+        let spans = self.fields.iter()
+            .chain(self.variants.iter().flat_map(|variant| variant.fields.iter()))
+            .map(|field| field.span)
+            .collect::<Vec<Span>>();
+        let trait_name = which.trait_name();
+        let krate = self.attributes.krate.as_deref().unwrap_or("::abes_nice_binary");
+        let code = format!("const _: () = {{ use {krate} as __abes_binary; {} }};", self.implement(which));
+        if flag(std::env::var("ABES_PROCS_PRINT_IMPL").ok()) {
+            eprintln!("{}", impl_note(trait_name, &self.name.to_string(), &code));
+        }
+        // So that cargo builds it again when ABES_PROCS_PRINT_IMPL changes,
+        // instead of keeping what was built without it and printing nothing
+        let code = format!("{code} const _: Option<&str> = option_env!(\"ABES_PROCS_PRINT_IMPL\");");
+        respan_fields(code.parse().unwrap(), &spans)
+    }
+    /// Puts what is needed for checksum and framed around the body of a read
+    fn wrap_read(&self, mut body: String) -> String {
+        if self.attributes.compress {
+            // Reading it through a Take means a bad length can't make it allocate everything
+            body = format!(
+                "let len = <u64 as __abes_binary::FromBinary>::from_binary(binary);\
+                let mut compressed = Vec::new();\
+                std::io::Read::read_to_end(&mut std::io::Read::take(&mut *binary, len), &mut compressed)\
+                    .expect(\"failed to read\");\
+                if (compressed.len() as u64) < len {{\
+                    panic!(\"ran out of data reading compressed {}\")\
+                }}\
+                let decompressed = __abes_binary::rle_decode(&compressed);\
+                let binary: &mut dyn std::io::Read = &mut &decompressed[..];\
+                {body}",
+                self.name
+            );
+        }
+        if self.attributes.checksum {
+            // Everything the body reads goes into the checksum
+            body = format!(
+                "let mut checked = __abes_binary::Crc32Reader::new(&mut *binary);\
+                let out = {{ let binary: &mut dyn std::io::Read = &mut checked; {body} }};\
+                let expected = checked.checksum();\
+                let found = <u32 as __abes_binary::FromBinary>::from_binary(binary);\
+                if found != expected {{\
+                    panic!(\"checksum mismatch for {}: expected {{expected:#010x}}, found {{found:#010x}}\")\
+                }}\
+                out",
+                self.name
+            );
+        }
+        if self.attributes.framed {
+            // Reading through a Take stops the body from reading past the frame,
+            // and anything it didn't read still gets skipped
+            let mut out = String::new();
+            out += "let len = <u64 as __abes_binary::FromBinary>::from_binary(binary);";
+            out += "let mut frame = std::io::Read::take(&mut *binary, len);";
+            out += "let out = { let binary: &mut dyn std::io::Read = &mut frame;";
+            out += &body;
+            out += "};";
+            out += "std::io::copy(&mut frame, &mut std::io::sink()).expect(\"failed to skip the rest of the frame\");";
+            out += "out";
+            body = out;
+        }
+        if let Some(align) = self.attributes.align {
+            // Counting what gets read to know how much padding there is after it
+            body = format!(
+                "let mut counted = __abes_binary::BinaryReader::new(&mut *binary);\
+                let out = {{ let binary: &mut dyn std::io::Read = &mut counted; {body} }};\
+                let read = counted.position() as usize;\
+                std::io::Read::read_exact(binary, &mut vec![0; read.next_multiple_of({align}) - read])\
+                    .expect(\"failed to read the padding of {}\");\
+                out",
+                self.name
+            );
+        }
+        body
+    }
+    /// Rounds the size before it up to what align pads it to
+    fn padded(&self) -> String {
+        match self.attributes.align {
+            Some(align) => format!(".next_multiple_of({align})"),
+            None => String::new()
+        }
+    }
+    /// The fields in the order they are written and read in.
+    /// Everything goes through this so that reading and writing can't disagree.
+    /// PhantomData doesn't have anything to write, so it gets left out
+    fn wire_fields(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter().filter(|field| !field.is_phantom())
+    }
+    /// Fills in the PhantomData fields that [DeriveData::wire_fields] left out
+    fn phantom_fields(&self) -> String {
+        self.fields.iter()
+            .filter(|field| field.is_phantom())
+            .map(|field| format!("{}: std::marker::PhantomData,", field.name))
+            .collect()
+    }
+    /// The first lifetime of the type, which is what zero_copy borrows for
+    fn lifetime(&self) -> Option<String> {
+        self.generic.windows(2).find_map(|pair| match pair {
+            [TokenTree::Punct(quote), TokenTree::Ident(name)] if quote.as_char() == '\'' => Some(format!("'{name}")),
+            _ => None
+        })
+    }
     fn implement(&self, which: Which) -> String {
         let mut out = String::new();
         match which {
+            Which::From if self.attributes.zero_copy => {
+                // It was checked that there is one when parsing
+                let lifetime = self.lifetime().unwrap();
+                let trait_name = format!("FromBinaryBorrowed<{lifetime}>");
+                out += &self.header(&trait_name);
+                out += &format!("{{ fn from_binary_borrowed(binary: &mut &{lifetime} [u8]) -> Self {{");
+                out += "Self {";
+                for field in self.wire_fields() {
+                    out += &for_field(field, &format!("{}: {},", field.name, field.read_borrowed(&lifetime)));
+                }
+                out += &self.phantom_fields();
+                out += "}}}";
+            }
             Which::From => {
-                out += "impl";
-                out += &self.generic.iter().map(|x| x.to_string()).collect::<String>();
-                out += " FromBinary for ";
-                out += &self.name.to_string();
-                // Second generic definition
-                for generic in self.generic.split(|x| {
-                    if let TokenTree::Punct(punct) = x {
-                        if punct.to_string() == "," {
-                            return true;
+                out += &self.header("FromBinary");
+                out += "{";
+                if self.attributes.strict {
+                    out += "const STRICT: bool = true;";
+                }
+                out += "fn from_binary(binary: &mut dyn std::io::Read) -> Self {";
+                let mut body = String::new();
+                if let What::Enum = self.what {
+                    body += "match ";
+                    body += self.attributes.discriminant.read();
+                    body += "{";
+                    for (index, variant) in self.variants.iter().enumerate() {
+                        body += &format!(
+                            "{} => {},",
+                            self.attributes.discriminant.pattern(index, &variant.name),
+                            variant.construct(|field| match field.is_phantom() {
+                                true => "std::marker::PhantomData".to_string(),
+                                false => for_field(field, &field.read("binary"))
+                            })
+                        );
+                    }
+                    match &self.attributes.unknown {
+                        // The frame skips whatever the variant had in it
+                        Some(unknown) => body += &format!("_ => Self::{unknown},"),
+                        None => body += &self.attributes.discriminant.invalid(&self.name)
+                    }
+                    body += "}";
+                }
+                else if self.attributes.tagged {
+                    // Fields that weren't written get defaulted
+                    // and fields that we don't know about get skipped
+                    body += "let count = <u32 as __abes_binary::FromBinary>::from_binary(binary);";
+                    body += "let out = Self {";
+                    // Anything a field was written with that it doesn't read gets skipped,
+                    // so that the next length is read from where it is
+                    for (index, field) in self.wire_fields().enumerate() {
+                        body += &for_field(field, &format!(
+                            "{}: if count > {index} {{\
+                                let len = <u64 as __abes_binary::FromBinary>::from_binary(binary);\
+                                let mut field = std::io::Read::take(&mut *binary, len);\
+                                let value = {};\
+                                std::io::copy(&mut field, &mut std::io::sink()).expect(\"failed to skip the rest of a field\");\
+                                value\
+                            }} else {{ Default::default() }},",
+                            field.name,
+                            field.read("&mut field")
+                        ));
+                    }
+                    body += &self.phantom_fields();
+                    body += "};";
+                    body += &format!(
+                        "for _ in {}..count {{\
+                            let len = <u64 as __abes_binary::FromBinary>::from_binary(binary);\
+                            std::io::copy(&mut std::io::Read::take(&mut *binary, len), &mut std::io::sink())\
+                                .expect(\"failed to skip unknown field\");\
+                        }}",
+                        self.wire_fields().count()
+                    );
+                    body += "out";
+                }
+                else if self.attributes.default_on_eof {
+                    body += "let mut ahead = __abes_binary::PeekReader::new(&mut *binary);";
+                    body += "Self {";
+                    for field in self.wire_fields() {
+                        body += &for_field(field, &format!(
+                            "{}: {},",
+                            field.name,
+                            field.read_unless_end(|read| read, "Default::default()")
+                        ));
+                    }
+                    body += &self.phantom_fields();
+                    body += "}";
+                }
+                else if self.attributes.default_rest {
+                    // Only the fields that were there get used,
+                    // with `..Default::default()` for the rest
+                    body += "let mut ahead = __abes_binary::PeekReader::new(&mut *binary);";
+                    let fields = self.wire_fields().collect::<Vec<&Field>>();
+                    for (index, field) in fields.iter().enumerate() {
+                        body += &for_field(field, &format!(
+                            "let read_{index} = {};",
+                            field.read_unless_end(|read| format!("Some({read})"), "None")
+                        ));
+                    }
+                    body += "match (";
+                    for index in 0..fields.len() {
+                        body += &format!("read_{index},");
+                    }
+                    body += ") {";
+                    for read in (0..=fields.len()).rev() {
+                        body += "(";
+                        for index in 0..fields.len() {
+                            body += &match index {
+                                _ if index < read => format!("Some(read_{index}),"),
+                                _ if index == read => "None,".to_string(),
+                                _ => "_,".to_string()
+                            };
                         }
+                        body += ") => Self {";
+                        for (index, field) in fields.iter().take(read).enumerate() {
+                            body += &format!("{}: read_{index},", field.name);
+                        }
+                        if read == fields.len() {
+                            body += &self.phantom_fields();
+                        }
+                        else {
+                            body += "..Default::default()";
+                        }
+                        body += "},";
+                    }
+                    body += "}";
+                }
+                else {
+                    // Each one is read into its own variable in the order they are written,
+                    // and then they are put in by name, so that the order
+                    // fields are declared in can't change which one gets what
+                    for (index, field) in self.wire_fields().enumerate() {
+                        body += &for_field(field, &format!("let read_{index} = {};", field.read("binary")));
+                    }
+                    body += "Self {";
+                    for (index, field) in self.wire_fields().enumerate() {
+                        body += &format!("{}: read_{index},", field.name);
+                    }
+                    body += &self.phantom_fields();
+                    body += "}";
+                }
+                out += &self.wrap_read(body);
+                out += "}";
+                if self.attributes.reuse {
+                    out += "fn from_binary_into(&mut self, binary: &mut dyn std::io::Read) {";
+                    let mut body = String::new();
+                    for field in self.wire_fields() {
+                        body += &for_field(field, &match field.wire_type {
+                            // There is nothing of the right type to read into then
+                            Some(_) => format!("self.{} = {};", field.name, field.read("binary")),
+                            None => format!("__abes_binary::FromBinary::from_binary_into(&mut self.{}, binary);", field.name)
+                        });
                     }
-                    return false;
-                }) {
-                    'inner: for token in generic.iter() {
-                        if let TokenTree::Punct(punct) = token {
-                            if punct.to_string() == ":" {
-                                break 'inner
-                            }
+                    out += &self.wrap_read(body);
+                    out += "}";
+                }
+                out += "}";
+            }
+            Which::To => {
+                out += &self.header("ToBinary");
+                out += "{ fn to_binary(&self, write: &mut dyn std::io::Write) {";
+                let mut body = String::new();
+                if let What::Enum = self.what {
+                    body += "match self {";
+                    for (index, variant) in self.variants.iter().enumerate() {
+                        body += &variant.pattern();
+                        body += "=> {";
+                        body += &self.attributes.discriminant.write(index, &variant.name);
+                        for field in variant.wire_fields() {
+                            body += &for_field(
+                                field,
+                                &format!("__abes_binary::ToBinary::to_binary({}, write);", field.written(&binding("field", field)))
+                            );
                         }
-                        out += &token.to_string();
+                        body += "}";
+                    }
+                    body += "}";
+                }
+                else if self.attributes.tagged {
+                    // Each field is written with its length so
+                    // that older versions can skip over it
+                    body += &format!("<u32 as __abes_binary::ToBinary>::to_binary(&{}, write);", self.wire_fields().count());
+                    for field in self.wire_fields() {
+                        body += &for_field(field, &format!(
+                            "{{\
+                                let mut buffer = Vec::new();\
+                                __abes_binary::ToBinary::to_binary({}, &mut buffer);\
+                                <u64 as __abes_binary::ToBinary>::to_binary(&(buffer.len() as u64), write);\
+                                std::io::Write::write_all(write, &buffer).expect(\"failed to write field\");\
+                            }}",
+                            field.written(&format!("&self.{}", field.name))
+                        ));
+                    }
+                }
+                else {
+                    for field in self.wire_fields() {
+                        body += &for_field(field, &format!(
+                            "__abes_binary::ToBinary::to_binary({}, write);",
+                            field.written(&format!("&self.{}", field.name))
+                        ));
                     }
-                    out += ",";
                 }
-                out.pop();
-                out += "{ fn from_binary(binary: &mut dyn std::io::Read) -> Self {";
-                for field in self.fields.iter() {
-                    out += "self.";
-                    out += &field.name;
-                    out += "=";
-                    out += &field.data_type;
-                    out += "::from_binary(binary),"
+                if self.attributes.compress {
+                    // The whole body has to be there to be compressed
+                    body = format!(
+                        "let mut uncompressed = Vec::new();\
+                        {{ let write: &mut dyn std::io::Write = &mut uncompressed; {body} }}\
+                        let compressed = __abes_binary::rle_encode(&uncompressed);\
+                        <u64 as __abes_binary::ToBinary>::to_binary(&(compressed.len() as u64), write);\
+                        std::io::Write::write_all(write, &compressed).expect(\"failed to write\");"
+                    );
+                }
+                if self.attributes.checksum {
+                    // The body has to be written somewhere else first to work out its checksum
+                    body = format!(
+                        "let mut checked = Vec::new();\
+                        {{ let write: &mut dyn std::io::Write = &mut checked; {body} }}\
+                        std::io::Write::write_all(write, &checked).expect(\"failed to write\");\
+                        <u32 as __abes_binary::ToBinary>::to_binary(&__abes_binary::crc32(&checked), write);"
+                    );
                 }
+                if self.attributes.framed {
+                    // The body has to be written somewhere else first to know its length
+                    body = format!(
+                        "let mut frame = Vec::new();\
+                        {{ let write: &mut dyn std::io::Write = &mut frame; {body} }}\
+                        <u64 as __abes_binary::ToBinary>::to_binary(&(frame.len() as u64), write);\
+                        std::io::Write::write_all(write, &frame).expect(\"failed to write frame\");"
+                    );
+                }
+                if let Some(align) = self.attributes.align {
+                    // Counting what gets written to know how much padding it needs
+                    body = format!(
+                        "let mut counted = __abes_binary::BinaryWriter::new(&mut *write);\
+                        {{ let write: &mut dyn std::io::Write = &mut counted; {body} }}\
+                        let written = counted.position() as usize;\
+                        std::io::Write::write_all(write, &vec![0; written.next_multiple_of({align}) - written])\
+                            .expect(\"failed to write padding\");"
+                    );
+                }
+                out += &body;
                 out += "}}";
             }
-            Which::To => {
-                // ToBinary
-                out += "impl";
-                out += &self.generic.iter().map(|x| x.to_string()).collect::<String>();
-                out += " ToBinary for ";
-                out += &self.name.to_string();
-                for generic in self.generic.split(|x| {
-                    if let TokenTree::Punct(punct) = x {
-                        if punct.to_string() == "," {
-                            return true;
+            Which::Size if self.attributes.compress => {
+                // There is no knowing how small it compresses without compressing it
+                out += &self.header_with_bound("BinarySize", "ToBinary");
+                out += "{ fn binary_size(&self) -> usize {";
+                out += "__abes_binary::ToBinary::to_binary_vec(self).len()";
+                out += "}}";
+            }
+            Which::Size => {
+                out += &self.header("BinarySize");
+                out += "{ fn binary_size(&self) -> usize {";
+                out += "(0";
+                if self.attributes.framed {
+                    // The length of the frame
+                    out += "+ 8";
+                }
+                if self.attributes.checksum {
+                    out += "+ 4";
+                }
+                if let What::Enum = self.what {
+                    out += "+ match self {";
+                    for (index, variant) in self.variants.iter().enumerate() {
+                        out += &variant.pattern();
+                        out += "=>";
+                        out += &self.attributes.discriminant.size(index, &variant.name);
+                        for field in variant.wire_fields() {
+                            out += &for_field(
+                                field,
+                                &format!("+ __abes_binary::BinarySize::binary_size({})", field.written(&binding("field", field)))
+                            );
                         }
+                        out += ",";
+                    }
+                    out += "}";
+                }
+                else {
+                    if self.attributes.tagged {
+                        // The field count, and the length of each field
+                        out += &format!("+ 4 + 8 * {}", self.wire_fields().count());
+                    }
+                    for field in self.wire_fields() {
+                        out += &for_field(field, &format!(
+                            "+ __abes_binary::BinarySize::binary_size({})",
+                            field.written(&format!("&self.{}", field.name))
+                        ));
                     }
-                    return false;
-                }) {
-                    'inner: for token in generic.iter() {
-                        if let TokenTree::Punct(punct) = token {
-                            if punct.to_string() == ":" {
-                                break 'inner
-                            }
+                }
+                out += ")";
+                out += &self.padded();
+                out += "}}";
+            }
+            Which::Diff => {
+                // Comparing what gets written means it doesn't need PartialEq
+                out += &self.header_with_bound("BinaryDiff", "ToBinary");
+                out += "{ fn binary_diff(&self, other: &Self) -> Vec<String> {";
+                out += "let mut out = Vec::new();";
+                let differs = |field: &Field, left: &str, right: &str, name: &str| for_field(field, &format!(
+                    "if __abes_binary::ToBinary::to_binary_vec({}) != __abes_binary::ToBinary::to_binary_vec({}) {{\
+                        out.push(String::from(\"{name}\"));\
+                    }}",
+                    field.written(left),
+                    field.written(right)
+                ));
+                if let What::Enum = self.what {
+                    out += "match (self, other) {";
+                    for variant in self.variants.iter() {
+                        out += &format!("({}, {}) => {{", variant.pattern_with("left"), variant.pattern_with("right"));
+                        for field in variant.wire_fields() {
+                            out += &differs(
+                                field,
+                                &binding("left", field),
+                                &binding("right", field),
+                                &format!("{}.{}", variant.name, field.name)
+                            );
                         }
-                        out += &token.to_string();
+                        out += "}";
                     }
-                    out += ",";
+                    // Being different variants is the only difference that matters then
+                    out += "_ => out.push(String::from(\"variant\"))";
+                    out += "}";
                 }
-                out.pop();
-                out += "{ fn to_binary(self, write: &mut dyn std::io::Write) {";
-                for field in self.fields.iter() {
-                    out += "self.";
-                    out += &field.name;
-                    out += ".to_binary(write);"
+                else {
+                    for field in self.wire_fields() {
+                        out += &differs(
+                            field,
+                            &format!("&self.{}", field.name),
+                            &format!("&other.{}", field.name),
+                            &field.name
+                        );
+                    }
                 }
+                out += "out";
                 out += "}}";
             }
+            Which::Fixed if self.attributes.compress => {
+                out += "compile_error!(\"FixedSize can't be derived with compress, since how small it gets depends on what is in it\");";
+            }
+            Which::Fixed => {
+                let fields_size = |fields: &mut dyn Iterator<Item = &Field>| fields
+                    .map(|field| for_field(field, &format!(
+                        "+ <{} as __abes_binary::FixedSize>::SERIALIZED_SIZE",
+                        field.wire_type.as_deref().unwrap_or(&field.data_type)
+                    )))
+                    .collect::<String>();
+                out += &self.header("FixedSize");
+                out += "{ const SERIALIZED_SIZE: usize = {";
+                out += "(0";
+                if self.attributes.framed {
+                    out += "+ 8";
+                }
+                if self.attributes.checksum {
+                    out += "+ 4";
+                }
+                // An enum without any variants can't be written at all, so it stays 0
+                if let What::Enum = self.what {
+                    if !self.variants.is_empty() {
+                        // Every variant has to be the same size for there to be one size,
+                        // which can only be known once the sizes of the fields are
+                        out += "+ { let sizes = [";
+                        for (index, variant) in self.variants.iter().enumerate() {
+                            out += &self.attributes.discriminant.fixed_size(index, &variant.name).to_string();
+                            out += &fields_size(&mut variant.wire_fields());
+                            out += ",";
+                        }
+                        out += &format!(
+                            "]; let mut index = 1;\
+                            while index < sizes.len() {{\
+                                if sizes[index] != sizes[0] {{\
+                                    panic!(\"every variant of {} has to be the same size for FixedSize\")\
+                                }}\
+                                index += 1;\
+                            }}\
+                            sizes[0] }}",
+                            self.name
+                        );
+                    }
+                }
+                else {
+                    if self.attributes.tagged {
+                        out += &format!("+ 4 + 8 * {}", self.wire_fields().count());
+                    }
+                    out += &fields_size(&mut self.wire_fields());
+                }
+                out += ")";
+                out += &self.padded();
+                out += "}; }";
+            }
         }
-        return out
+        out
     }
 }
 impl From<TokenStream> for DeriveData {
     fn from(value: TokenStream) -> Self {
-        let mut iter = value.into_iter();
+        DeriveData::try_from(value).unwrap_or_else(|error| panic!("{error}"))
+    }
+}
+impl DeriveData {
+    /// Parses the struct or enum the derive is on,
+    /// giving back what is wrong with it instead of panicking
+    fn try_from(value: TokenStream) -> Result<Self, String> {
+        let mut iter = value.into_iter().peekable();
         let mut what: Option<What> = None;
+        let mut attributes = Attributes::default();
+        // Only attributes and the visibility can come before struct/enum,
+        // so anything else means something is wrong
         while let Some(token) = iter.next() {
-            if let TokenTree::Ident(ident) = token {
-                if let Some(wht) = What::from_ident(ident) {
-                    what = Some(wht);
-                    break;
+            match token {
+                TokenTree::Punct(punct) if punct.as_char() == '#' => match iter.next() {
+                    Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                        attributes.parse(group.stream())?;
+                    }
+                    other => return Err(format!("expected #[...], found #{}", other.map(|x| x.to_string()).unwrap_or_default()))
+                },
+                TokenTree::Ident(ident) if ident.to_string() == "pub" => {
+                    // For pub(crate) and the like
+                    if let Some(TokenTree::Group(group)) = iter.peek() {
+                        if group.delimiter() == Delimiter::Parenthesis {
+                            iter.next();
+                        }
+                    }
                 }
+                TokenTree::Ident(ident) => match What::from_ident(ident.clone()) {
+                    Some(wht) => {
+                        what = Some(wht);
+                        break;
+                    }
+                    None => return Err(format!("expected a struct or enum, found {ident}"))
+                },
+                other => return Err(format!("expected a struct or enum, found {other}"))
             }
         }
-        let what = what.expect("Missing what it is(struct/enum)");
-        let name_tree = iter.next().expect("Missing name");
-        let name;
-        if let TokenTree::Ident(ident) = name_tree {
-            name = ident;
-        }
-        else {
-            panic!("FUCK FUCK FUCK FUCK FUCK FUCK")
-        }
+        let what = what.ok_or("expected a struct or enum, found nothing")?;
+        let name = match iter.next() {
+            Some(TokenTree::Ident(name)) => name,
+            Some(other) => return Err(format!("expected a name, found {other}")),
+            None => return Err("expected a name, found nothing".to_string())
+        };
         let mut generic = Vec::new();
         let mut fields_stream: Option<Vec<TokenTree>> = None;
-        while let Some(token) = iter.next() {
-            if let TokenTree::Group(group) = token {
-                fields_stream = Some(group.stream().into_iter().collect());
-                break;
-            }
-            else {
-                generic.push(token);
+        let mut tuple = false;
+        // Groups can be inside of the generics too, like the () in `<F: Fn() -> u8>`,
+        // so only one outside of every <> is the fields,
+        // and after a where clause starts it has to be {} (like `where F: Fn() -> u8 {`)
+        let mut depth = 0_usize;
+        let mut in_where = false;
+        for token in iter {
+            match token {
+                TokenTree::Ident(ref ident) if depth == 0 && ident.to_string() == "where" => in_where = true,
+                TokenTree::Group(group) if depth == 0 && (!in_where || group.delimiter() == Delimiter::Brace) => {
+                    tuple = group.delimiter() == Delimiter::Parenthesis;
+                    fields_stream = Some(group.stream().into_iter().collect());
+                    break;
+                }
+                TokenTree::Punct(ref punct) if punct.as_char() == '<' => depth += 1,
+                // The > in -> isn't closing anything
+                TokenTree::Punct(ref punct) if punct.as_char() == '>' && !matches!(
+                    generic.last(),
+                    Some(TokenTree::Punct(before)) if before.as_char() == '-'
+                ) => depth = depth.saturating_sub(1),
+                _ => {}
             }
+            generic.push(token);
+        }
+        // A where clause comes after the generics, and goes on every impl as it is
+        let mut where_clause = Vec::new();
+        if let Some(index) = generic.iter().position(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "where")) {
+            where_clause = generic.split_off(index);
+            where_clause.remove(0);
         }
-        let fields_stream = fields_stream.expect("Could not get fields");
+        let fields_stream = fields_stream.ok_or_else(|| format!("expected the fields of {name}, found nothing"))?;
         let mut fields = Vec::new();
-        for field_tokens in fields_stream.split(|x| {
-            if let TokenTree::Punct(punct) = x {
-                if punct.to_string() == ",".to_string() {
-                    return true
+        let mut variants = Vec::new();
+        match what {
+            // Otherwise the types would get taken as names that are missing their types
+            What::Struct if tuple => return Err(format!(
+                "{name} is a tuple struct, which can't be derived, its fields need names like `name: Type`"
+            )),
+            What::Struct => {
+                // Everything else reads through a &mut dyn Read, which can't be borrowed from
+                if attributes.zero_copy && (
+                    attributes.tagged || attributes.framed || attributes.checksum || attributes.compress
+                    || attributes.align.is_some() || attributes.default_on_eof || attributes.default_rest || attributes.reuse || attributes.strict
+                ) {
+                    return Err("zero_copy can't be used with any other options".to_string())
+                }
+                if attributes.zero_copy && !generic.iter().any(|token| matches!(token, TokenTree::Punct(quote) if quote.as_char() == '\'')) {
+                    return Err(format!("zero_copy needs {name} to have a lifetime for what it borrows from"))
+                }
+                if attributes.default_rest && (attributes.tagged || attributes.default_on_eof) {
+                    return Err("default_rest can't be used with tagged or default_on_eof".to_string())
+                }
+                // Fields that weren't written or are unknown have nothing to read into,
+                // so those options don't work with this
+                if attributes.reuse && (attributes.tagged || attributes.default_on_eof || attributes.default_rest) {
+                    return Err("reuse can't be used with tagged, default_on_eof, or default_rest".to_string())
+                }
+                fields = parse_fields(&fields_stream, true)?;
+                if let Some(order) = &attributes.order {
+                    fields = reorder(fields, order, &name.to_string())?;
                 }
             }
-            return false
-        }) {
-            fields.push(Field {
-                name: field_tokens[0].to_string(),
-                data_type: {
-                    field_tokens[2..].iter().map(|x| x.to_string()).collect::<String>()
+            What::Enum => {
+                if attributes.tagged {
+                    return Err("tagged can only be used on structs".to_string())
+                }
+                if attributes.default_on_eof {
+                    return Err("default_on_eof can only be used on structs".to_string())
+                }
+                if attributes.default_rest {
+                    return Err("default_rest can only be used on structs".to_string())
+                }
+                if attributes.reuse {
+                    return Err("reuse can only be used on structs".to_string())
+                }
+                if attributes.order.is_some() {
+                    return Err("order can only be used on structs".to_string())
+                }
+                if attributes.zero_copy {
+                    return Err("zero_copy can only be used on structs".to_string())
+                }
+                if attributes.unknown.is_some() && !attributes.framed {
+                    return Err("unknown needs framed too, otherwise there is no way to skip an unknown variant".to_string())
+                }
+                for variant_tokens in split_top_level(&fields_stream, ',') {
+                    // The discriminant(like `= 5`) isn't used
+                    let variant_tokens = skip_attributes(split_top_level(variant_tokens, '=')[0]);
+                    match variant_tokens {
+                        [] => {}
+                        [TokenTree::Ident(name)] => variants.push(Variant {
+                            name: name.to_string(),
+                            fields: Vec::new(),
+                            named: false
+                        }),
+                        [TokenTree::Ident(name), TokenTree::Group(group)]
+                            if matches!(group.delimiter(), Delimiter::Parenthesis | Delimiter::Brace) =>
+                        {
+                            let named = group.delimiter() == Delimiter::Brace;
+                            variants.push(Variant {
+                                name: name.to_string(),
+                                fields: parse_fields(&group.stream().into_iter().collect::<Vec<TokenTree>>(), named)?,
+                                named
+                            })
+                        }
+                        [TokenTree::Ident(name), ..] => return Err(format!(
+                            "variant {name} can't be derived, it has to be a unit, tuple, or struct variant"
+                        )),
+                        other => return Err(format!(
+                            "expected a variant, found {}",
+                            other.iter().cloned().collect::<TokenStream>()
+                        ))
+                    }
                 }
-            })
+            }
+        }
+        // Numbering every field so for_field can find them again
+        for (id, field) in fields.iter_mut()
+            .chain(variants.iter_mut().flat_map(|variant| variant.fields.iter_mut()))
+            .enumerate()
+        {
+            field.id = id;
+        }
+        if let Some(unknown) = &attributes.unknown {
+            if !variants.iter().any(|variant| &variant.name == unknown && variant.fields.is_empty()) {
+                return Err(format!("unknown = {unknown} has to be a unit variant of {name}"))
+            }
         }
-        DeriveData {
+        Ok(DeriveData {
             what,
             name,
             generic,
-            fields
+            where_clause,
+            fields,
+            variants,
+            attributes
+        })
+    }
+}
+/// Skips the attributes(including doc comments) at the start
+fn skip_attributes(mut tokens: &[TokenTree]) -> &[TokenTree] {
+    while let [TokenTree::Punct(punct), TokenTree::Group(_), rest @ ..] = tokens {
+        if punct.as_char() != '#' {
+            break
+        }
+        tokens = rest;
+    }
+    tokens
+}
+/// Puts the fields in the order from `order(...)`,
+/// which has to have every one of them once
+fn reorder(mut fields: Vec<Field>, order: &[String], name: &str) -> Result<Vec<Field>, String> {
+    let mut out = Vec::new();
+    for field_name in order {
+        match fields.iter().position(|field| &field.name == field_name) {
+            Some(index) => out.push(fields.remove(index)),
+            None if out.iter().any(|field: &Field| &field.name == field_name) => {
+                return Err(format!("{field_name} is in the order more than once"))
+            }
+            None => return Err(format!("{name} doesn't have a field called {field_name}"))
+        }
+    }
+    if let Some(missing) = fields.first() {
+        return Err(format!("the order is missing {} out of {name}", missing.name))
+    }
+    Ok(out)
+}
+/// If the type uses an associated type of one of the generic types,
+/// as in `T::Assoc` or `<T as Trait>::Assoc`
+fn projects(tokens: &[TokenTree], types: &[String]) -> bool {
+    tokens.iter().enumerate().any(|(index, token)| match token {
+        TokenTree::Group(group) => projects(&group.stream().into_iter().collect::<Vec<TokenTree>>(), types),
+        TokenTree::Ident(ident) if types.contains(&ident.to_string()) => match &tokens[index + 1..] {
+            [TokenTree::Punct(colon), ..] if colon.as_char() == ':' => true,
+            [TokenTree::Ident(keyword), ..] => keyword.to_string() == "as",
+            _ => false
+        },
+        _ => false
+    })
+}
+/// Gets the fields out of the inside of {} or (),
+/// naming the fields of the () by their position
+fn parse_fields(tokens: &[TokenTree], named: bool) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+    for field_tokens in split_top_level(tokens, ',') {
+        // Attributes come before the name
+        let mut wire_type = None;
+        let mut field_tokens = field_tokens;
+        while let [TokenTree::Punct(punct), TokenTree::Group(group), rest @ ..] = field_tokens {
+            if punct.as_char() != '#' {
+                break
+            }
+            if let Some(found) = field_attribute(group.stream())? {
+                wire_type = Some(found);
+            }
+            field_tokens = rest;
+        }
+        // Then the visibility, which could be pub or pub(...)
+        if let [TokenTree::Ident(ident), rest @ ..] = field_tokens {
+            if ident.to_string() == "pub" {
+                field_tokens = match rest {
+                    [TokenTree::Group(group), after @ ..] if group.delimiter() == Delimiter::Parenthesis => after,
+                    _ => rest
+                };
+            }
+        }
+        // A trailing comma leaves nothing after it
+        if field_tokens.is_empty() {
+            continue
+        }
+        let (name, data_type) = match (named, field_tokens) {
+            (true, [TokenTree::Ident(name), TokenTree::Punct(colon), data_type @ ..])
+                if colon.as_char() == ':' && !data_type.is_empty() => (name.to_string(), data_type),
+            (true, [TokenTree::Ident(name)]) => return Err(format!(
+                "the field `{name}` is missing its type, it should be like `{name}: Type`"
+            )),
+            (true, [TokenTree::Ident(name), TokenTree::Punct(colon)]) if colon.as_char() == ':' => return Err(format!(
+                "the field `{name}` is missing its type after the `:`"
+            )),
+            (true, _) => return Err(format!(
+                "expected a field like `name: Type`, found `{}`",
+                field_tokens.iter().cloned().collect::<TokenStream>()
+            )),
+            (false, data_type) => (fields.len().to_string(), data_type)
+        };
+        let span = data_type.first().map_or_else(Span::call_site, TokenTree::span);
+        // Going through TokenStream keeps things like :: and 'a together
+        // while still putting spaces where they're needed
+        let data_type = data_type.iter().cloned().collect::<TokenStream>().to_string();
+        fields.push(Field {
+            name,
+            wire_type: wire_type.map(|wire_type| match wire_type {
+                WireType::As(wire_type) => wire_type,
+                WireType::Cstr => format!("__abes_binary::NulTerminated<{data_type}>")
+            }),
+            data_type,
+            span,
+            id: 0
+        })
+    }
+    Ok(fields)
+}
+/// What a field is written as, from the options on it
+enum WireType {
+    /// `binary(as = Type)`
+    As(String),
+    /// `binary(cstr)`, which is NulTerminated of whatever the field is
+    Cstr
+}
+/// Takes the inside of a `#[...]` on a field, giving back what `binary(...)` says to write it as
+fn field_attribute(attribute: TokenStream) -> Result<Option<WireType>, String> {
+    let mut iter = attribute.into_iter();
+    if !matches!(iter.next(), Some(TokenTree::Ident(ident)) if ident.to_string() == "binary") {
+        return Ok(None)
+    }
+    let options = match iter.next() {
+        Some(TokenTree::Group(group)) => group.stream().into_iter().collect::<Vec<TokenTree>>(),
+        _ => return Err("expected binary(...)".to_string())
+    };
+    let mut wire_type = None;
+    for option in split_top_level(&options, ',') {
+        match option {
+            [] => {}
+            [TokenTree::Ident(key), TokenTree::Punct(eq), value @ ..]
+                if key.to_string() == "as" && eq.as_char() == '=' && !value.is_empty() =>
+            {
+                wire_type = Some(WireType::As(value.iter().cloned().collect::<TokenStream>().to_string()));
+            }
+            [TokenTree::Ident(key), ..] if key.to_string() == "as" => return Err("expected as = Type".to_string()),
+            [TokenTree::Ident(key)] if key.to_string() == "cstr" => wire_type = Some(WireType::Cstr),
+            [other, ..] => return Err(format!("unknown binary option for a field: {other}"))
         }
     }
+    Ok(wire_type)
 }
 impl std::fmt::Display for DeriveData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "name: {}\n", self.name)?;
-        write!(f, "what: {}\n", self.what)?;
-        write!(f, "generic: {:?}\n", self.generic.iter().map(|x| x.to_string()).collect::<Vec<String>>())?;
-        write!(f, "fields: {:?}", self.fields)
+        writeln!(f, "name: {}", self.name)?;
+        writeln!(f, "what: {}", self.what)?;
+        writeln!(f, "generic: {:?}", self.generic.iter().map(|x| x.to_string()).collect::<Vec<String>>())?;
+        writeln!(f, "fields: {:?}", self.fields)?;
+        write!(f, "variants: {:?}", self.variants)
     }
 }
 #[proc_macro_derive(Test)]
+/// For debugging the derives.
+/// (Setting ABES_PROCS_PRINT_IMPL to 1 is easier when only the impls are needed,
+/// which prints every one the other derives make to the build output)
+/// Writes how the input was tokenized to token.txt,
+/// what was parsed out of it to data.txt,
+/// and the generated FromBinary impl to out.txt.
+///
+/// The tokenized dump is also given as `const TOKEN_DUMP: &str`
+/// so it can be checked in tests.
+/// Every token is on its own line as `ident: `, `literal: `, or `punct: `
+/// and groups are `group:` with what is inside indented by a tab.
 pub fn test(input: TokenStream) -> TokenStream {
     let mut out = String::new();
-    printer(&input, 0, &mut out);
+    printer(&input, 0, PRINTER_DEPTH, &mut out);
+    let dump = format!("const TOKEN_DUMP: &str = {};", Literal::string(&out));
     let data = DeriveData::from(input);
     std::fs::write("token.txt", out).unwrap();
     std::fs::write("data.txt", data.to_string()).unwrap();
     std::fs::write("out.txt", data.implement(Which::From)).unwrap();
-    TokenStream::new()
+    dump.parse().unwrap()
+}
+#[proc_macro]
+/// Gives back the tokens it was given without changing them,
+/// along with a dump of how they were tokenized.
+/// This is meant for debugging other macros.
+///
+/// The dump is put in a doc comment on a `const _`
+/// right before the tokens, so it can be read
+/// by expanding the macro (like with cargo expand).
+/// Because of that, this has to be used where items can go.
+/// Anything more than 64 groups deep is left out of the dump as `...truncated...`.
+///```
+/// # use abes_nice_procs::dump_tokens;
+/// dump_tokens! {
+///     fn five() -> u8 { 5 }
+/// }
+/// // Expands to something like
+/// // #[doc = "ident: fn\nident: five\ngroup:\n..."]
+/// // const _: () = ();
+/// // fn five() -> u8 { 5 }
+/// # fn main() {
+/// assert_eq!(five(), 5);
+/// # }
+///```
+pub fn dump_tokens(input: TokenStream) -> TokenStream {
+    let mut dump = String::new();
+    printer(&input, 0, PRINTER_DEPTH, &mut dump);
+    let mut out = format!("#[doc = {}] const _: () = ();", Literal::string(&dump))
+        .parse::<TokenStream>()
+        .unwrap();
+    out.extend(input);
+    out
 }
-fn printer(input: &TokenStream, layer: usize, out: &mut String) {
+/// How many groups deep [printer] goes before it stops
+const PRINTER_DEPTH: usize = 64;
+/// Writes out every token on its own line, with what is in groups indented.
+/// Groups more than max_depth in just get `...truncated...`,
+/// so something nested really deep can't run out of stack
+fn printer(input: &TokenStream, layer: usize, max_depth: usize, out: &mut String) {
     for i in input.clone().into_iter() {
         match i.clone() {
             TokenTree::Group(group) => {
                 *out += &"\t".repeat(layer);
                 *out += "group:\n";
-                printer(&group.stream(), layer+1, out)
+                if layer + 1 >= max_depth {
+                    *out += &"\t".repeat(layer + 1);
+                    *out += "...truncated...\n";
+                }
+                else {
+                    printer(&group.stream(), layer+1, max_depth, out)
+                }
             }
             TokenTree::Ident(ident) => {
                 *out += &"\t".repeat(layer);
@@ -378,20 +2799,569 @@ fn printer(input: &TokenStream, layer: usize, out: &mut String) {
         }
     }
 }
+/// Marks the code as being for the field, so that [respan_fields]
+/// can make errors in it point at the field instead of the derive.
+/// The code gets put in as is, the marker is only so it can be found.
+fn for_field(field: &Field, code: &str) -> String {
+    format!(" __abes_field_{}! {{ {code} }} ", field.id)
+}
+/// Replaces what [for_field] made with the code inside of it,
+/// which has been given where the field is.
+/// Only where it is, the names in it still resolve like the rest of the impl,
+/// otherwise a field type from a macro_rules! would make `self` and the rest unknown
+fn respan_fields(tokens: TokenStream, spans: &[Span]) -> TokenStream {
+    fn set_span(tokens: TokenStream, span: Span) -> TokenStream {
+        tokens.into_iter().map(|mut token| {
+            if let TokenTree::Group(group) = &token {
+                token = TokenTree::Group(Group::new(group.delimiter(), set_span(group.stream(), span)));
+            }
+            token.set_span(span);
+            token
+        }).collect()
+    }
+    let mut out = TokenStream::new();
+    let mut iter = tokens.into_iter().peekable();
+    while let Some(token) = iter.next() {
+        match token {
+            TokenTree::Ident(ident) if ident.to_string().starts_with("__abes_field_") => {
+                let id = ident.to_string()["__abes_field_".len()..].parse::<usize>().unwrap();
+                iter.next();// The !
+                let Some(TokenTree::Group(group)) = iter.next() else { unreachable!() };
+                out.extend(set_span(group.stream(), Span::call_site().located_at(spans[id])));
+            }
+            TokenTree::Group(group) => {
+                let mut new = Group::new(group.delimiter(), respan_fields(group.stream(), spans));
+                new.set_span(group.span());
+                out.extend([TokenTree::Group(new)]);
+            }
+            other => out.extend([other])
+        }
+    }
+    out
+}
+/// Splits tokens on a punctuation character,
+/// ignoring any inside of <> like the comma in `HashMap<K, V>`
+fn split_top_level(tokens: &[TokenTree], split: char) -> Vec<&[TokenTree]> {
+    let mut out = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        let TokenTree::Punct(punct) = token else { continue };
+        match punct.as_char() {
+            '<' => depth += 1,
+            // The > in -> isn't closing anything
+            '>' if !matches!(
+                index.checked_sub(1).map(|before| &tokens[before]),
+                Some(TokenTree::Punct(before)) if before.as_char() == '-'
+            ) => depth = depth.saturating_sub(1),
+            c if c == split && depth == 0 => {
+                out.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(&tokens[start..]);
+    out
+}
 #[derive(Debug)]
 struct Field {
     name: String,
     data_type: String,
+    /// Where the type is, for errors about it
+    span: Span,
+    /// Which field this is out of all of them, see [for_field]
+    id: usize,
+    /// What it is written as instead, set by `#[binary(as = Type)]`,
+    /// or `#[binary(cstr)]` which is `NulTerminated<Type>`
+    wire_type: Option<String>,
+}
+impl Field {
+    /// The code that reads it from `binary`
+    fn read(&self, binary: &str) -> String {
+        self.read_with(|read_type| format!("<{read_type} as __abes_binary::FromBinary>::from_binary({binary})"))
+    }
+    /// The same as [Field::read], but through FromBinaryBorrowed for zero_copy
+    fn read_borrowed(&self, lifetime: &str) -> String {
+        self.read_with(|read_type| format!("<{read_type} as __abes_binary::FromBinaryBorrowed<{lifetime}>>::from_binary_borrowed(binary)"))
+    }
+    /// Reads it from the PeekReader `ahead` with `present` around the read,
+    /// or gives `missing` when there is nothing left.
+    /// Zero sized fields don't read anything,
+    /// so checking for them would take a byte from whatever comes after
+    fn read_unless_end(&self, present: impl Fn(String) -> String, missing: &str) -> String {
+        let read_type = self.wire_type.as_ref().unwrap_or(&self.data_type);
+        format!(
+            "if std::mem::size_of::<{read_type}>() != 0 && ahead.at_end() {{\
+                {missing}\
+            }} else {{\
+                {}\
+            }}",
+            present(self.read("&mut ahead"))
+        )
+    }
+    /// Reads the type it is written as with `read`, turning it back into what it is
+    fn read_with(&self, read: impl Fn(&str) -> String) -> String {
+        match &self.wire_type {
+            Some(wire_type) => format!(
+                "match <{data_type} as std::convert::TryFrom<{wire_type}>>::try_from({read}) {{\
+                    Ok(value) => value,\
+                    Err(_) => panic!(\"{name} doesn't fit in {data_type}\")\
+                }}",
+                read = read(wire_type),
+                data_type = self.data_type,
+                name = self.name
+            ),
+            None => read(&self.data_type)
+        }
+    }
+    /// What actually gets written for it, from a reference to it.
+    /// With `as`, it panics instead of cutting off a value that doesn't fit
+    fn written(&self, value: &str) -> String {
+        match &self.wire_type {
+            Some(wire_type) => format!(
+                "&match <{wire_type} as std::convert::TryFrom<{data_type}>>::try_from(Clone::clone({value})) {{\
+                    Ok(value) => value,\
+                    Err(_) => panic!(\"{name} doesn't fit in {wire_type}\")\
+                }}",
+                data_type = self.data_type,
+                name = self.name
+            ),
+            None => value.to_string()
+        }
+    }
+    /// If it is a PhantomData, from anywhere it could be used from
+    fn is_phantom(&self) -> bool {
+        let tokens = self.data_type.parse::<TokenStream>()
+            .unwrap()
+            .into_iter()
+            .take_while(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == '<'))
+            .collect::<Vec<TokenTree>>();
+        matches!(tokens.last(), Some(TokenTree::Ident(ident)) if ident.to_string() == "PhantomData")
+    }
+}
+#[derive(Debug)]
+struct Variant {
+    name: String,
+    /// The fields inside the () named by their position,
+    /// or inside the {} with their names
+    fields: Vec<Field>,
+    /// If the fields are in {} instead of ()
+    named: bool
+}
+impl Variant {
+    /// Makes `Self::Name(field0, field1)` (or `Self::Name { a: fielda }`) to match on the variant
+    fn pattern(&self) -> String {
+        self.pattern_with("field")
+    }
+    /// The same as [Variant::pattern], but with something else in place of `field`
+    fn pattern_with(&self, prefix: &str) -> String {
+        self.construct(|field| binding(prefix, field))
+    }
+    /// The same as [DeriveData::wire_fields], for the fields of the variant
+    fn wire_fields(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter().filter(|field| !field.is_phantom())
+    }
+    /// Makes `Self::Name(...)` or `Self::Name { ... }` with what `value` gives for each field
+    fn construct(&self, value: impl Fn(&Field) -> String) -> String {
+        let mut out = format!("Self::{}", self.name);
+        if self.named {
+            out += "{";
+            for field in self.fields.iter() {
+                out += &format!("{}: {},", field.name, value(field));
+            }
+            out += "}";
+        }
+        else if !self.fields.is_empty() {
+            out += "(";
+            for field in self.fields.iter() {
+                out += &format!("{},", value(field));
+            }
+            out += ")";
+        }
+        out
+    }
+}
+/// What a field of a variant is called when it gets matched,
+/// like `field0`, or `fieldname` for a named one
+fn binding(prefix: &str, field: &Field) -> String {
+    format!("{prefix}{}", variant_name(&field.name))
 }
 enum Which {
     From,
-    To
+    To,
+    Size,
+    Diff,
+    Fixed
+}
+impl Which {
+    fn trait_name(&self) -> &'static str {
+        match self {
+            Which::From => "FromBinary",
+            Which::To => "ToBinary",
+            Which::Size => "BinarySize",
+            Which::Diff => "BinaryDiff",
+            Which::Fixed => "FixedSize"
+        }
+    }
+}
+/// What ABES_PROCS_PRINT_IMPL prints for each derive
+fn impl_note(trait_name: &str, name: &str, code: &str) -> String {
+    format!("note: the {trait_name} impl for {name} is:\n{code}")
+}
+/// Does everything for the derives,
+/// with anything wrong with the input becoming a compile error
+fn derive(input: TokenStream, which: Which) -> TokenStream {
+    match DeriveData::try_from(input) {
+        Ok(data) => data.expand(which),
+        Err(error) => {
+            // It is where items go, which needs the ; after it
+            let mut out = compile_error(&error, Span::call_site());
+            out.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+            out
+        }
+    }
 }
-#[proc_macro_derive(FromBinary)]
+#[proc_macro_derive(FromBinary, attributes(binary))]
 pub fn from_binary(input: TokenStream) -> TokenStream {
-    DeriveData::from(input).implement(Which::From).parse::<TokenStream>().unwrap()
+    derive(input, Which::From)
 }
-#[proc_macro_derive(ToBinary)]
+#[proc_macro_derive(ToBinary, attributes(binary))]
 pub fn to_binary(input: TokenStream) -> TokenStream {
-    DeriveData::from(input).implement(Which::To).parse::<TokenStream>().unwrap()
-}
\ No newline at end of file
+    derive(input, Which::To)
+}
+#[proc_macro_derive(BinarySize, attributes(binary))]
+/// Implements BinarySize, which gives how many bytes
+/// ToBinary would write without having to write it.
+pub fn binary_size(input: TokenStream) -> TokenStream {
+    derive(input, Which::Size)
+}
+#[proc_macro_derive(BinaryDiff, attributes(binary))]
+/// Implements BinaryDiff, which lists the fields of two values
+/// that get written differently, for finding which one broke
+/// when something doesn't round trip.
+pub fn binary_diff(input: TokenStream) -> TokenStream {
+    derive(input, Which::Diff)
+}
+#[proc_macro_derive(FixedSize, attributes(binary))]
+/// Implements FixedSize, for things that ToBinary always writes
+/// the same number of bytes for, which needs every field to be FixedSize.
+/// Every variant of an enum has to be the same size too,
+/// which is checked when it gets compiled.
+pub fn fixed_size(input: TokenStream) -> TokenStream {
+    derive(input, Which::Fixed)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_compile_cleans_up() {
+        let result = run("failed_compile_cleanup", "fn main() { this is not rust }", &Options::default());
+        assert!(result.is_err());
+        let stray = std::fs::read_dir(".")
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("failed_compile_cleanup"))
+            .collect::<Vec<String>>();
+        assert!(stray.is_empty(), "stray files: {stray:?}");
+    }
+    #[test]
+    fn inherited_rustflags() {
+        let options = Options { rustflags: rustflags(None, Some("--cfg abes_test_flag".to_string())), ..Default::default() };
+        let code = "fn main() { print!(\"{}\", cfg!(abes_test_flag)) }";
+        assert_eq!(run("inherited_rustflags", code, &options).as_deref(), Ok("true"));
+        assert_eq!(run("ignored_rustflags", code, &Options::default()).as_deref(), Ok("false"));
+        // The encoded ones can have spaces in them and come first
+        assert_eq!(
+            rustflags(Some("--cfg\x1fa b".to_string()), Some("--cfg c".to_string())),
+            ["--cfg", "a b"]
+        );
+        assert!(rustflags(Some(String::new()), None).is_empty());
+    }
+    #[test]
+    fn build_dir_reused() {
+        let dir = std::env::temp_dir().join(format!("abes_procs_build_{}", std::process::id()));
+        let options = Options { build_dir: Some(dir.clone()), ..Default::default() };
+        let code = "fn main() { print!(\"built\") }";
+        assert_eq!(run("build_dir_reused", code, &options).as_deref(), Ok("built"));
+        let built = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_none_or(|extension| extension != "rs"))
+            .collect::<Vec<PathBuf>>();
+        assert_eq!(built.len(), 1, "{built:?}");
+        let modified = || std::fs::metadata(&built[0]).unwrap().modified().unwrap();
+        let first = modified();
+        // Nothing went in the crate root
+        assert!(!Path::new("build_dir_reused.rs").exists());
+        assert_eq!(run("build_dir_reused", code, &options).as_deref(), Ok("built"));
+        let second = modified();
+        // Different code gets built separately
+        assert_eq!(run("build_dir_reused", "fn main() { print!(\"other\") }", &options).as_deref(), Ok("other"));
+        _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(first, second);
+        assert_eq!(
+            build_dir("target/abes", "/crate", Some(PathBuf::from("/elsewhere"))),
+            Path::new("/elsewhere/abes")
+        );
+        assert_eq!(build_dir("target/abes", "/crate", None), Path::new("/crate/target/abes"));
+        assert_eq!(build_dir("out", "/crate", Some(PathBuf::from("/elsewhere"))), Path::new("/crate/out"));
+    }
+    #[test]
+    fn print_impl() {
+        assert!(flag(Some("1".to_string())));
+        assert!(flag(Some("yes".to_string())));
+        assert!(!flag(Some("0".to_string())));
+        assert!(!flag(Some(String::new())));
+        assert!(!flag(None));
+        let note = impl_note(Which::Size.trait_name(), "Thing", "impl BinarySize for Thing {}");
+        assert_eq!(note, "note: the BinarySize impl for Thing is:\nimpl BinarySize for Thing {}");
+    }
+    #[test]
+    fn env_cleared() {
+        // cargo sets this for the tests, so it is always there to not be passed on
+        assert!(std::env::var("CARGO_MANIFEST_DIR").is_ok());
+        let code = "fn main() { print!(\"{}\", std::env::var(\"CARGO_MANIFEST_DIR\").is_ok()) }";
+        let options = Options { env_allow: Some(Vec::new()), ..Default::default() };
+        assert_eq!(run("env_cleared", code, &options).as_deref(), Ok("false"));
+        let options = Options { env_allow: Some(vec!["CARGO_MANIFEST_DIR".to_string()]), ..Default::default() };
+        assert_eq!(run("env_allowed", code, &options).as_deref(), Ok("true"));
+        assert_eq!(run("env_inherited", code, &Options::default()).as_deref(), Ok("true"));
+    }
+    #[test]
+    fn jobs() {
+        assert_eq!(job_limit(Some("3".to_string())), Ok(3));
+        assert!(job_limit(None).is_ok_and(|jobs| jobs > 0));
+        assert_eq!(job_limit(Some("0".to_string())), Err("ABES_PROCS_JOBS should be a number above 0, not \"0\"".to_string()));
+        assert!(job_limit(Some("many".to_string())).is_err());
+    }
+    #[test]
+    fn skipped() {
+        let tracked = tracked_env();
+        assert!(tracked.contains("const _: Option<&str> = option_env!(\"ABES_PROCS_SKIP\");"));
+        assert_eq!(stub(None, &Output::Tokens, false), None);
+        assert_eq!(stub(Some("0".to_string()), &Output::Tokens, false), None);
+        assert_eq!(stub(Some("1".to_string()), &Output::Tokens, false), Some(format!("{{ {tracked} Default::default() }}")));
+        assert_eq!(stub(Some("1".to_string()), &Output::Str, false), Some(format!("{{ {tracked} \"\" }}")));
+        assert_eq!(stub(Some("1".to_string()), &Output::Status, false), Some(format!("{{ {tracked} 0 }}")));
+        // Where items go, it is only what tracks the variable
+        assert_eq!(stub(Some("1".to_string()), &Output::Tokens, true).as_deref(), Some(tracked.as_str()));
+        assert_eq!(stub(Some("1".to_string()), &Output::Items, true).as_deref(), Some(tracked.as_str()));
+        assert_eq!(stub(Some("1".to_string()), &Output::Nothing, true).as_deref(), Some(tracked.as_str()));
+    }
+    #[test]
+    fn guessed_items() {
+        assert!(starts_items("fn generated() -> u8 {{"));
+        assert!(starts_items("  pub struct"));
+        assert!(starts_items("#[derive(Debug)]"));
+        assert!(starts_items(""));
+        assert!(!starts_items("{}"));
+        assert!(!starts_items("[1, "));
+        assert!(!starts_items("function"));
+        assert!(!starts_items("starting"));
+    }
+    #[test]
+    fn param_types() {
+        assert_eq!(param_type("5").as_deref(), Some("i64"));
+        assert_eq!(param_type("- 5").as_deref(), Some("i64"));
+        assert_eq!(param_type("0xE5").as_deref(), Some("i64"));
+        assert_eq!(param_type("0xf64").as_deref(), Some("i64"));
+        assert_eq!(param_type("1.5").as_deref(), Some("f64"));
+        assert_eq!(param_type("1e3").as_deref(), Some("f64"));
+        assert_eq!(param_type("5u8").as_deref(), Some("u8"));
+        assert_eq!(param_type("2.0f32").as_deref(), Some("f32"));
+        assert_eq!(param_type("\"name\"").as_deref(), Some("&str"));
+        assert_eq!(param_type("r#\"name\"#").as_deref(), Some("&str"));
+        assert_eq!(param_type("'c'").as_deref(), Some("char"));
+        assert_eq!(param_type("true").as_deref(), Some("bool"));
+        assert_eq!(param_type("[1, 2]"), None);
+        assert_eq!(param_type("OTHER"), None);
+    }
+    #[test]
+    fn keep_files() {
+        let options = Options { keep: true, ..Default::default() };
+        let output = run("kept_files", "fn main() { print!(\"kept\") }", &options);
+        assert_eq!(output.as_deref(), Ok("kept"));
+        let rs_path = Path::new("kept_files.rs");
+        let bin_path = Path::new(".").join(format!("kept_files{}", std::env::consts::EXE_SUFFIX));
+        let kept = (rs_path.exists(), bin_path.exists());
+        _ = std::fs::remove_file(rs_path);
+        _ = std::fs::remove_file(&bin_path);
+        _ = std::fs::remove_file("kept_files.pdb");
+        assert_eq!(kept, (true, true));
+    }
+    #[test]
+    fn built_for_host() {
+        let code = "fn main() { print!(\"{} {}\", std::env::consts::ARCH, std::env::consts::OS) }";
+        let output = run("built_for_host", code, &Options::default());
+        // The tests are always built for the host, so they should be the same
+        assert_eq!(output, Ok(format!("{} {}", std::env::consts::ARCH, std::env::consts::OS)));
+        assert!(!host_triple(&Options::default()).unwrap().is_empty());
+    }
+    #[test]
+    fn timeout() {
+        let options = Options { timeout: Some(Duration::from_millis(500)), ..Default::default() };
+        let code = "fn main() { std::thread::sleep(std::time::Duration::from_secs(30)) }";
+        let start = Instant::now();
+        let error = run("timed_out", code, &options).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert!(error.contains("timeout of 500ms"), "{error}");
+        // Finishing in time is fine
+        let code = "fn main() { print!(\"done\") }";
+        let options = Options { timeout: Some(Duration::from_secs(30)), ..Default::default() };
+        assert_eq!(run("in_time", code, &options).as_deref(), Ok("done"));
+    }
+    #[test]
+    fn missing_rustc() {
+        let options = Options { rustc: Some("/does/not/exist/rustc".to_string()), ..Default::default() };
+        let error = run("missing_rustc", "fn main() {}", &options).unwrap_err();
+        assert!(error.contains("method! needs rustc"), "{error}");
+        assert!(error.contains("/does/not/exist/rustc"), "{error}");
+        assert!(error.contains("RUSTC"), "{error}");
+    }
+    #[test]
+    fn not_utf8() {
+        let code = "use std::io::Write; fn main() { std::io::stdout().write_all(b\"let x = \\xff\\xfe;\").unwrap() }";
+        let error = run("not_utf8", code, &Options::default()).unwrap_err();
+        assert!(error.contains("isn't utf8"), "{error}");
+        assert!(error.contains("index 8"), "{error}");
+        assert!(error.contains("b\"let x = \\xff\\xfe;\""), "{error}");
+    }
+    #[test]
+    #[ignore = "needs the nightly toolchain"]
+    fn nightly_toolchain() {
+        let options = Options { toolchain: Some("nightly".to_string()), ..Default::default() };
+        let code = "#![feature(never_type)] fn main() { let never: Option<!> = None; print!(\"{}\", never.is_none()) }";
+        assert_eq!(run("nightly_toolchain", code, &options).as_deref(), Ok("true"));
+        // Which stable can't do
+        assert!(run("stable_toolchain", code, &Options::default()).is_err());
+    }
+    #[test]
+    fn missing_toolchain() {
+        let options = Options { toolchain: Some("not-a-real-toolchain".to_string()), ..Default::default() };
+        let error = run("missing_toolchain", "fn main() {}", &options).unwrap_err();
+        assert!(error.contains("not-a-real-toolchain"), "{error}");
+    }
+    #[test]
+    fn formatted() {
+        let options = Options { fmt: true, ..Default::default() };
+        let code = "fn main() { print!(\"fn formatted()->u8{{1+2}}\") }";
+        assert_eq!(run("formatted", code, &options).as_deref(), Ok("fn formatted() -> u8 {\n    1 + 2\n}\n"));
+        // Expressions can't be formatted, so they are left how they are
+        let code = "fn main() { print!(\"1+2\") }";
+        assert_eq!(run("not_formatted", code, &options).as_deref(), Ok("1+2"));
+        assert_eq!(format_output("1+2", "2021"), Err("rustfmt failed (error: expected item, found `1`)".to_string()));
+    }
+    #[test]
+    fn editions() {
+        assert_eq!(edition("[package]\nedition = \"2024\"").as_deref(), Ok("2024"));
+        let error = edition("[package]\nedition = \"2O21\"").unwrap_err();
+        assert!(error.contains("edition \"2O21\""), "{error}");
+        assert!(error.contains("2015, 2018, 2021, 2024"), "{error}");
+    }
+    #[test]
+    fn cargo_driver() {
+        let code = "fn main() { print!(\"{}\", (1..=4).product::<u32>()) }";
+        let from_rustc = run("driven_by_rustc", code, &Options::default());
+        let options = Options { driver: Driver::Cargo, ..Default::default() };
+        let from_cargo = run("driven_by_cargo", code, &options);
+        assert_eq!(from_rustc.as_deref(), Ok("24"));
+        assert_eq!(from_cargo, from_rustc);
+        assert!(!Path::new("driven_by_cargo").exists());
+    }
+    #[test]
+    fn encodings() {
+        // From RFC 4648
+        for (bytes, base64) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy")
+        ] {
+            assert_eq!(Encoding::Base64.encode(bytes.as_bytes()), base64);
+        }
+        assert_eq!(Encoding::Hex.encode(b"foobar"), "666f6f626172");
+        let code = "use std::io::Write; fn main() { std::io::stdout().write_all(&[0xff, 0, 0xfe]).unwrap() }";
+        let options = Options { encode: Some(Encoding::Base64), ..Default::default() };
+        assert_eq!(run("encoded", code, &options).as_deref(), Ok("/wD+"));
+    }
+    #[test]
+    fn trailing_newline() {
+        let code = "fn main() { println!(\"5\") }";
+        assert_eq!(run("with_newline", code, &Options::default()).as_deref(), Ok("5"));
+        let options = Options { keep_newline: true, ..Default::default() };
+        assert_eq!(run("kept_newline", code, &options).as_deref(), Ok("5\n"));
+        // Only one gets taken off
+        let code = "fn main() { print!(\"5\\n\\n\") }";
+        assert_eq!(run("two_newlines", code, &Options::default()).as_deref(), Ok("5\n"));
+    }
+    #[test]
+    fn recorded() {
+        let dir = std::env::temp_dir().join(format!("abes_procs_record_{}", std::process::id()));
+        let options = Options { record_dir: Some(dir.clone()), ..Default::default() };
+        let code = "fn main() { print!(\"recorded\") }";
+        let output = run("recorded", code, &options);
+        let files = (
+            std::fs::read_to_string(dir.join("recorded.rs")),
+            std::fs::read_to_string(dir.join("recorded.out"))
+        );
+        _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(output.as_deref(), Ok("recorded"));
+        assert_eq!(files.0.as_deref().ok(), Some(code));
+        assert_eq!(files.1.as_deref().ok(), Some("recorded"));
+        // Which isn't the same as keeping the files
+        assert!(!Path::new("recorded.rs").exists());
+    }
+    #[test]
+    fn child_panic() {
+        let code = "fn main() {\n    panic!(\"the code gave up\");\n}";
+        let error = run("child_panic", code, &Options::default()).unwrap_err();
+        assert!(error.contains("the code gave up"), "{error}");
+        assert!(error.contains("child_panic.rs:2"), "{error}");
+        let options = Options { backtrace: true, ..Default::default() };
+        let error = run("child_backtrace", code, &options).unwrap_err();
+        assert!(error.contains("stack backtrace"), "{error}");
+    }
+    #[test]
+    fn cached() {
+        // The time is different every run, so getting it twice means it only ran once
+        let call = |path: &str, code: &str| Call {
+            path: path.to_string(),
+            code: code.to_string(),
+            options: Options { cache_key: Some("cached".to_string()), ..Default::default() },
+            tracked: Vec::new()
+        };
+        let code = "fn main() { print!(\"{:?}\", std::time::SystemTime::now()) }";
+        let first = run_cached(&call("cached_first", code)).unwrap();
+        assert_eq!(run_cached(&call("cached_second", code)), Ok(first));
+        let error = run_cached(&call("cached_other", "fn main() {}")).unwrap_err();
+        assert!(error.contains("already used for different code"), "{error}");
+    }
+    #[test]
+    fn parallel() {
+        let calls = ["parallel_a", "parallel_b", "parallel_c"].map(|path| Call {
+            path: path.to_string(),
+            code: format!("fn main() {{ std::thread::sleep(std::time::Duration::from_secs(1)); print!(\"{path}\") }}"),
+            options: Options::default(),
+            tracked: Vec::new()
+        });
+        let calls = calls.iter().collect::<Vec<&Call>>();
+        let start = Instant::now();
+        let serial = calls.iter()
+            .map(|call| run(&call.path, &call.code, &call.options))
+            .collect::<Vec<_>>();
+        let serial_time = start.elapsed();
+        let start = Instant::now();
+        let parallel = run_all(&calls, 3);
+        let parallel_time = start.elapsed();
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel[1].as_deref(), Ok("parallel_b"));
+        assert!(parallel_time < serial_time, "{parallel_time:?} vs {serial_time:?}");
+        // Only going one at a time still gets all of them
+        assert_eq!(run_all(&calls, 1), serial);
+    }
+}
@@ -0,0 +1,4 @@
+// A generator used by the include tests, which makes an item
+fn main() {
+    print!("fn included() -> u8 {{ 9 }}");
+}
@@ -0,0 +1,4 @@
+// A generator used by the include tests
+fn main() {
+    print!("{}", 3 + 4);
+}
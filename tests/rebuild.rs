@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Makes a crate called name that depends on this one, with main as its main.rs
+fn project(name: &str, main: &str) -> PathBuf {
+    let project = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::write(
+        project.join("Cargo.toml"),
+        format!(
+            "[package]\n\
+            name = {:?}\n\
+            version = \"0.1.0\"\n\
+            edition = \"2021\"\n\
+            [dependencies]\n\
+            abes_nice_procs = {{ path = {:?} }}\n\
+            abes_nice_binary = {{ path = {:?} }}\n\
+            [workspace]\n",
+            name,
+            env!("CARGO_MANIFEST_DIR"),
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("binary")
+        )
+    ).unwrap();
+    // Using the same versions of everything so it can build offline
+    let lock = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.lock");
+    if lock.exists() {
+        std::fs::copy(lock, project.join("Cargo.lock")).unwrap();
+    }
+    std::fs::write(project.join("src/main.rs"), main).unwrap();
+    project
+}
+/// Runs the crate, giving back what it printed
+fn run(project: &Path, env: &[(&str, &str)]) -> String {
+    let output = Command::new(env!("CARGO"))
+        .arg("run")
+        .arg("--quiet")
+        .envs(env.iter().copied())
+        .current_dir(project)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+/// Builds the crate, giving back what the build printed
+fn build(project: &Path, env: &[(&str, &str)]) -> String {
+    let output = Command::new(env!("CARGO"))
+        .arg("build")
+        .arg("--quiet")
+        .envs(env.iter().copied())
+        .current_dir(project)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{stderr}");
+    stderr
+}
+/// Builds and runs a crate using method! with a declared dependency,
+/// changes the dependency, and checks that the output changed too
+#[test]
+fn changed_dependency_rebuilds() {
+    let project = project(
+        "rebuild",
+        "fn main() {\n\
+            print!(\"{}\", abes_nice_procs::method!(read_data, deps = [\"data.txt\"],\n\
+                fn main() {\n\
+                    print!(\"{}\", std::fs::read_to_string(\"data.txt\").unwrap())\n\
+                }\n\
+            ));\n\
+        }\n"
+    );
+    std::fs::write(project.join("data.txt"), "1").unwrap();
+    assert_eq!(run(&project, &[]), "1");
+    // Making sure the modified time is different
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    std::fs::write(project.join("data.txt"), "2").unwrap();
+    assert_eq!(run(&project, &[]), "2");
+}
+/// With ABES_PROCS_SKIP, the code isn't run and the calls become stand ins,
+/// and the crate gets built again whenever it is set or unset
+#[test]
+fn skipped() {
+    let project = project(
+        "skipped",
+        "abes_nice_procs::method!(skipped_items, print!(\"#[allow(dead_code)] fn generated() -> u32 {{ 5 }}\"));\n\
+        fn main() {\n\
+            let value: u32 = abes_nice_procs::method!(skipped_value, print!(\"2\"));\n\
+            print!(\"{value}\");\n\
+        }\n"
+    );
+    assert_eq!(run(&project, &[]), "2");
+    assert_eq!(run(&project, &[("ABES_PROCS_SKIP", "1")]), "0");
+    assert_eq!(run(&project, &[]), "2");
+}
+/// Setting ABES_PROCS_RECORD_DIR on a crate that was already built
+/// runs the calls again so they get recorded
+#[test]
+fn recorded() {
+    let project = project("recorded", "fn main() { print!(\"{}\", abes_nice_procs::method!(recorded, print!(\"4\"))); }\n");
+    assert_eq!(run(&project, &[]), "4");
+    for dir in ["first", "second"] {
+        let dir = project.join(dir);
+        // Left over from the last time the test ran
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(run(&project, &[("ABES_PROCS_RECORD_DIR", dir.to_str().unwrap())]), "4");
+        assert_eq!(std::fs::read_to_string(dir.join("recorded.out")).unwrap(), "4");
+    }
+}
+/// ABES_PROCS_PRINT_IMPL prints the impls to the build output,
+/// even when the crate was already built without it
+#[test]
+fn print_impl() {
+    let project = project(
+        "print_impl",
+        "#[derive(abes_nice_procs::ToBinary)]\n\
+        struct Printed {\n\
+            value: u8\n\
+        }\n\
+        fn main() {\n\
+            print!(\"{}\", abes_nice_binary::ToBinary::to_binary_vec(&Printed { value: 3 })[0]);\n\
+        }\n"
+    );
+    assert!(!build(&project, &[]).contains("impl for Printed"));
+    let printed = build(&project, &[("ABES_PROCS_PRINT_IMPL", "1")]);
+    assert!(printed.contains("note: the ToBinary impl for Printed is:"), "{printed}");
+    assert!(printed.contains("__abes_binary::ToBinary for Printed"), "{printed}");
+    assert_eq!(run(&project, &[]), "3");
+}
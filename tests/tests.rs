@@ -1,4 +1,5 @@
 use abes_nice_procs::*;
+use abes_nice_binary::*;
 
 #[test]
 fn test() {
@@ -8,9 +9,2036 @@ fn test() {
         }
     ), 5);
 }
+#[test]
+fn method_file_names() {
+    assert_eq!(method!(ident_name, print!("1")), 1);
+    assert_eq!(method!(r#raw_name, print!("2")), 2);
+    assert_eq!(method!("string_name", print!("3")), 3);
+    assert_eq!(method!("string-name-2", print!("4")), 4);
+}
+#[test]
+fn method_without_main() {
+    assert_eq!(method!(without_main,
+        let value = 2 + 2;
+        print!("{value}");
+    ), 4);
+}
+#[test]
+fn method_without_main_attributes() {
+    // recursion_limit only does anything for the whole file, so it can't be in main
+    assert_eq!(method!(without_main_attributes,
+        #![recursion_limit = "256"]
+        macro_rules! count {
+            () => { 0 };
+            (x $($rest:tt)*) => { 1 + count!($($rest)*) };
+        }
+        print!("{}", count!(
+            x x x x x x x x x x x x x x x x x x x x x x x x x x
+            x x x x x x x x x x x x x x x x x x x x x x x x x x
+            x x x x x x x x x x x x x x x x x x x x x x x x x x
+            x x x x x x x x x x x x x x x x x x x x x x x x x x
+            x x x x x x x x x x x x x x x x x x x x x x x x x x
+        ));
+    ), 130);
+}
+#[test]
+fn method_with_helpers() {
+    assert_eq!(method!(with_helpers,
+        fn main() {
+            print!("{}", helper());
+        }
+        fn helper() -> u8 {
+            6
+        }
+    ), 6);
+}
 #[derive(Test)]
+#[allow(dead_code)]
 pub struct Asd<T> {
     help: usize,
     banan: Option<Vec<i128>>,
     nawr: T
-}
\ No newline at end of file
+}
+
+#[allow(dead_code)]
+mod dump {
+    use super::*;
+
+    #[derive(Test)]
+    struct Point {
+        x: u8,
+        y: Vec<u8>
+    }
+
+    #[test]
+    fn snapshot() {
+        assert_eq!(TOKEN_DUMP, "\
+ident: struct
+ident: Point
+group:
+\tident: x
+\tpunct: :
+\tident: u8
+\tpunct: ,
+\tident: y
+\tpunct: :
+\tident: Vec
+\tpunct: <
+\tident: u8
+\tpunct: >
+");
+    }
+
+    mod deep {
+        use super::*;
+
+        // Writing out this many () by hand would be a lot
+        method!(deep_tokens, print!(
+            "#[derive(Test)] struct Deep {{ value: {}u8{} }}",
+            "(".repeat(100),
+            ",)".repeat(100)
+        ));
+
+        #[test]
+        fn truncated() {
+            assert!(TOKEN_DUMP.contains("...truncated..."), "{TOKEN_DUMP}");
+            let deepest = TOKEN_DUMP.lines()
+                .map(|line| line.len() - line.trim_start_matches('\t').len())
+                .max();
+            assert_eq!(deepest, Some(64));
+            assert!(!TOKEN_DUMP.contains("ident: u8"));
+        }
+    }
+}
+
+mod tagged {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(tagged)]
+    struct Old {
+        a: u32,
+        b: String
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(tagged)]
+    struct New {
+        a: u32,
+        b: String,
+        c: u64
+    }
+
+    #[test]
+    fn old_into_new() {
+        let mut binary = Vec::new();
+        Old { a: 1, b: "old".to_string() }.to_binary(&mut binary);
+        assert_eq!(
+            New::from_binary(&mut binary.as_slice()),
+            New { a: 1, b: "old".to_string(), c: 0 }
+        );
+    }
+    #[test]
+    fn new_into_old() {
+        let mut binary = Vec::new();
+        New { a: 1, b: "new".to_string(), c: 2 }.to_binary(&mut binary);
+        5_u8.to_binary(&mut binary);
+        let mut read = binary.as_slice();
+        assert_eq!(Old::from_binary(&mut read), Old { a: 1, b: "new".to_string() });
+        // The unknown field has to be skipped over entirely
+        assert_eq!(u8::from_binary(&mut read), 5);
+    }
+    #[test]
+    fn longer_field() {
+        // Like a newer version that made a's type bigger
+        let b = "b".to_string().to_binary_vec();
+        let mut binary = Vec::new();
+        2_u32.to_binary(&mut binary);
+        8_u64.to_binary(&mut binary);
+        7_u64.to_binary(&mut binary);
+        (b.len() as u64).to_binary(&mut binary);
+        binary.extend(b);
+        assert_eq!(Old::from_binary_slice(&binary), Old { a: 7, b: "b".to_string() });
+    }
+}
+
+dump_tokens! {
+    fn dumped() -> (u8, &'static str) {
+        (5, "five")
+    }
+}
+#[test]
+fn dump_tokens_passthrough() {
+    assert_eq!(dumped(), (5, "five"));
+}
+
+mod fields {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct TrailingComma {
+        a: u8,
+        b: u16,
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct TrailingLong {
+        a: u32,
+        b: Vec<u8>,
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Config<'a> {
+        name: std::borrow::Cow<'a, str>,
+        path: std::borrow::Cow<'a, [u8]>
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Attributed {
+        /// Documented
+        a: u8,
+        #[allow(dead_code)]
+        b: u16
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    pub struct Public {
+        pub a: u8,
+        /// Documented
+        pub(crate) b: u16,
+        pub(super) c: u8
+    }
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Raw {
+        r#type: u32,
+        r#match: bool
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Paths {
+        a: ::std::string::String,
+        b: std::collections::BTreeMap<std::primitive::u8, Vec<::std::primitive::u16>>
+    }
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct One {
+        x: u32
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct OneComma {
+        x: u32,
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct OneLong {
+        x: Vec<Option<u8>>
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct OneLongComma {
+        x: Vec<Option<u8>>,
+    }
+
+    #[derive(ToBinary, FromBinary, BinarySize, FixedSize, Debug, PartialEq, Clone, Copy)]
+    struct Cell {
+        value: u8,
+        marked: bool
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, FixedSize, Debug, PartialEq)]
+    struct Grid {
+        cells: [Cell; 9],
+        rows: [[u8; 3]; 2 + 1]
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Row<const N: usize> {
+        cells: [Cell; N]
+    }
+
+    trait Named {
+        fn name(&self) -> &str;
+    }
+    impl Named for u8 {
+        fn name(&self) -> &str {
+            "u8"
+        }
+    }
+    impl ToBinary for Box<dyn Named> {
+        fn to_binary(&self, write: &mut dyn std::io::Write) {
+            self.name().to_string().to_binary(write)
+        }
+    }
+    impl ToBinary for Box<dyn Named + Send> {
+        fn to_binary(&self, write: &mut dyn std::io::Write) {
+            self.name().to_string().to_binary(write)
+        }
+    }
+    #[derive(ToBinary)]
+    struct Traits {
+        a: Box<dyn Named>,
+        b: Box<dyn Named + Send>,
+        c: Box<dyn Named + Send + 'static>
+    }
+
+    #[test]
+    fn trait_objects() {
+        let mut binary = Vec::new();
+        Traits { a: Box::new(1), b: Box::new(2), c: Box::new(3) }.to_binary(&mut binary);
+        assert_eq!(binary, [2, 0, 0, 0, 0, 0, 0, 0, b'u', b'8'].repeat(3));
+    }
+    /// A doc comment
+    /// that goes on for a while
+    #[derive(Clone)]
+    #[repr(C)]
+    #[doc = "struct NotTheName"]
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(ToBinary, FromBinary, BinarySize)]
+    pub struct Decorated {
+        a: u16,
+        b: u8
+    }
+    #[repr(u8)]
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    /// Documented after an attribute
+    pub(crate) enum DecoratedEnum {
+        A,
+        B
+    }
+
+    #[test]
+    fn decorated() {
+        let mut binary = Vec::new();
+        Decorated { a: 1, b: 2 }.clone().to_binary(&mut binary);
+        assert_eq!(binary, [1, 0, 2]);
+        assert_eq!(Decorated { a: 1, b: 2 }.binary_size(), 3);
+        assert_eq!(Decorated::from_binary_slice(&binary).b, 2);
+        assert_eq!(DecoratedEnum::from_binary_slice(&DecoratedEnum::B.to_binary_vec()), DecoratedEnum::B);
+    }
+    // Only the visibility can have a group before the name,
+    // which shouldn't end up in the generics or as the name
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    pub(crate) struct CrateVisible {
+        a: u8
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    pub(in crate::fields) struct PathVisible<T> {
+        a: T
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    pub(super) enum SuperVisible {
+        A(u8)
+    }
+
+    #[test]
+    fn restricted_visibility() {
+        let binary = CrateVisible { a: 1 }.to_binary_vec();
+        assert_eq!(CrateVisible::from_binary_slice(&binary), CrateVisible { a: 1 });
+        let binary = PathVisible { a: 2_u16 }.to_binary_vec();
+        assert_eq!(binary, [2, 0]);
+        assert_eq!(PathVisible::<u16>::from_binary_slice(&binary), PathVisible { a: 2 });
+        let binary = SuperVisible::A(3).to_binary_vec();
+        assert_eq!(SuperVisible::from_binary_slice(&binary), SuperVisible::A(3));
+    }
+    // Fields that are cfg'd out are gone by the time the derive sees them
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Configured {
+        a: u8,
+        #[cfg(feature = "cargo-driver")]
+        #[allow(dead_code)]
+        feature: u16,
+        #[cfg(test)]
+        /// Always there
+        present: u8,
+        #[cfg(not(test))]
+        absent: u32,
+        #[cfg_attr(test, allow(dead_code))]
+        b: u8
+    }
+
+    #[test]
+    fn cfg_fields() {
+        let value = Configured {
+            a: 1,
+            #[cfg(feature = "cargo-driver")]
+            feature: 2,
+            present: 3,
+            b: 4
+        };
+        let binary = value.to_binary_vec();
+        if cfg!(feature = "cargo-driver") {
+            assert_eq!(binary, [1, 2, 0, 3, 4]);
+        }
+        else {
+            assert_eq!(binary, [1, 3, 4]);
+        }
+        assert_eq!(value.binary_size(), binary.len());
+        assert_eq!(Configured::from_binary_slice(&binary), value);
+    }
+    #[test]
+    fn arrays() {
+        let cell = |value| Cell { value, marked: value % 2 == 0 };
+        let grid = Grid {
+            cells: std::array::from_fn(|index| cell(index as u8)),
+            rows: [[1, 2, 3], [4, 5, 6], [7, 8, 9]]
+        };
+        let binary = grid.to_binary_vec();
+        // Arrays don't have a length written
+        assert_eq!(binary.len(), 9 * 2 + 9);
+        assert_eq!(binary[..4], [0, 1, 1, 0]);
+        assert_eq!(binary.len(), grid.binary_size());
+        assert_eq!(Grid::SERIALIZED_SIZE, binary.len());
+        assert_eq!(Grid::from_binary_slice(&binary), grid);
+
+        let row = Row { cells: [cell(3), cell(4)] };
+        assert_eq!(Row::<2>::from_binary_slice(&row.to_binary_vec()), row);
+    }
+    #[test]
+    fn one_field() {
+        let mut with = Vec::new();
+        let mut without = Vec::new();
+        One { x: 5 }.to_binary(&mut without);
+        OneComma { x: 5 }.to_binary(&mut with);
+        assert_eq!(with, without);
+        assert_eq!(One::from_binary(&mut with.as_slice()), One { x: 5 });
+        assert_eq!(OneComma::from_binary(&mut without.as_slice()), OneComma { x: 5 });
+
+        let mut with = Vec::new();
+        let mut without = Vec::new();
+        OneLong { x: vec![Some(1), None] }.to_binary(&mut without);
+        OneLongComma { x: vec![Some(1), None] }.to_binary(&mut with);
+        assert_eq!(with, without);
+        assert_eq!(with, [2, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0]);
+        assert_eq!(OneLong::from_binary(&mut with.as_slice()), OneLong { x: vec![Some(1), None] });
+        assert_eq!(OneLongComma::from_binary(&mut without.as_slice()), OneLongComma { x: vec![Some(1), None] });
+    }
+    #[test]
+    fn trailing_comma() {
+        let mut binary = Vec::new();
+        TrailingComma { a: 1, b: 2 }.to_binary(&mut binary);
+        assert_eq!(binary, [1, 2, 0]);
+        assert_eq!(TrailingComma::from_binary(&mut binary.as_slice()), TrailingComma { a: 1, b: 2 });
+    }
+    #[test]
+    fn cow_fields() {
+        use std::borrow::Cow;
+        let config = Config { name: Cow::Borrowed("config"), path: Cow::Borrowed(b"/etc") };
+        let binary = config.to_binary_vec();
+        assert_eq!(binary.len(), config.binary_size());
+        let read = Config::from_binary_slice(&binary);
+        assert_eq!(read, config);
+        assert!(matches!(read.name, Cow::Owned(_)));
+    }
+    #[test]
+    fn trailing_comma_after_long_type() {
+        let value = TrailingLong { a: 1, b: vec![2, 3] };
+        let binary = value.to_binary_vec();
+        assert_eq!(binary, [1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2, 3]);
+        assert_eq!(value.binary_size(), binary.len());
+        assert_eq!(TrailingLong::from_binary_slice(&binary), value);
+    }
+    #[test]
+    fn attributed() {
+        let mut binary = Vec::new();
+        Attributed { a: 1, b: 2 }.to_binary(&mut binary);
+        assert_eq!(binary, [1, 2, 0]);
+        assert_eq!(Attributed::from_binary(&mut binary.as_slice()), Attributed { a: 1, b: 2 });
+    }
+    #[test]
+    fn raw_identifiers() {
+        let mut binary = Vec::new();
+        Raw { r#type: 1, r#match: true }.to_binary(&mut binary);
+        assert_eq!(binary, [1, 0, 0, 0, 1]);
+        assert_eq!(Raw::from_binary(&mut binary.as_slice()), Raw { r#type: 1, r#match: true });
+    }
+    #[test]
+    fn paths() {
+        let value = Paths {
+            a: "path".to_string(),
+            b: std::collections::BTreeMap::from([(1, vec![2, 3])])
+        };
+        let mut binary = Vec::new();
+        Paths { a: value.a.clone(), b: value.b.clone() }.to_binary(&mut binary);
+        assert_eq!(Paths::from_binary(&mut binary.as_slice()), value);
+    }
+    #[test]
+    fn public() {
+        let mut binary = Vec::new();
+        Public { a: 1, b: 2, c: 3 }.to_binary(&mut binary);
+        assert_eq!(binary, [1, 2, 0, 3]);
+        assert_eq!(Public::from_binary(&mut binary.as_slice()), Public { a: 1, b: 2, c: 3 });
+    }
+}
+
+method!(formatted_items, fmt = true,
+    print!("fn formatted_a()->u8{{ 1 }} fn formatted_b(x:u8)->u8{{x*2}}");
+);
+#[test]
+fn method_formatted() {
+    // Formatting doesn't change what the output means
+    assert_eq!(formatted_a(), 1);
+    assert_eq!(formatted_b(3), 6);
+}
+#[test]
+fn method_include() {
+    assert_eq!(method!(include_expression, include = "tests/generators/seven.rs"), 7);
+}
+method!(include_items, include = "tests/generators/items.rs");
+#[test]
+fn method_include_items() {
+    assert_eq!(included(), 9);
+}
+
+mod generics {
+    use super::*;
+    use std::marker::PhantomData;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct S<T: Into<u8>, U = i32> {
+        a: T,
+        b: U
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Nested {
+        // The comma in here isn't between fields
+        inner: S<u8, u16>,
+        after: u8
+    }
+    // Closures can't be written, so this stands in for a `F: Fn() -> u8` bound
+    trait Source<F> {
+        fn source(&self) -> F;
+    }
+    impl Source<fn() -> u8> for u8 {
+        fn source(&self) -> fn() -> u8 {
+            || 4
+        }
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Grouped<T: Source<fn() -> u8>, B: AsRef<[u8]>> {
+        source: T,
+        bytes: B
+    }
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Tagged<T> {
+        value: u32,
+        _marker: std::marker::PhantomData<T>
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(tagged)]
+    struct TaggedPhantom<T> {
+        _marker: PhantomData<T>,
+        value: u8
+    }
+
+    #[test]
+    fn phantom_data() {
+        let tagged = Tagged::<String> { value: 7, _marker: PhantomData };
+        let binary = tagged.to_binary_vec();
+        assert_eq!(binary, [7, 0, 0, 0]);
+        assert_eq!(tagged.binary_size(), 4);
+        assert_eq!(Tagged::<String>::from_binary_slice(&binary), tagged);
+        // The marker isn't counted as a field either
+        let tagged = TaggedPhantom::<u8> { _marker: PhantomData, value: 3 };
+        let binary = tagged.to_binary_vec();
+        assert_eq!(binary, [1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(TaggedPhantom::<u8>::from_binary_slice(&binary), tagged);
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, FixedSize, Debug, PartialEq)]
+    enum PhantomEnum<T> {
+        A(u8, std::marker::PhantomData<String>),
+        B { value: u8, _marker: PhantomData<T> }
+    }
+
+    #[test]
+    fn phantom_data_enum() {
+        let value = PhantomEnum::<u8>::A(7, PhantomData);
+        let binary = value.to_binary_vec();
+        assert_eq!(binary, [0, 0, 0, 0, 7]);
+        assert_eq!(value.binary_size(), 5);
+        assert_eq!(PhantomEnum::<u8>::SERIALIZED_SIZE, 5);
+        assert_eq!(PhantomEnum::<u8>::from_binary_slice(&binary), value);
+        let value = PhantomEnum::<u8>::B { value: 3, _marker: PhantomData };
+        assert_eq!(PhantomEnum::<u8>::from_binary_slice(&value.to_binary_vec()), value);
+        assert!(value.binary_diff(&PhantomEnum::B { value: 3, _marker: PhantomData }).is_empty());
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Deeper<T: std::fmt::Debug = Vec<Vec<u8>>, const N: usize = 2> {
+        inner: T,
+        nested: Vec<Option<Vec<u8>>>
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct HoldsDeeper {
+        deeper: Deeper<Deeper<Vec<Vec<u8>>, 1>>,
+        other: Box<Deeper>
+    }
+
+    #[test]
+    fn nested_generics() {
+        let holds = HoldsDeeper {
+            deeper: Deeper {
+                inner: Deeper { inner: vec![vec![1]], nested: vec![None] },
+                nested: vec![Some(vec![2]), None]
+            },
+            other: Box::new(Deeper { inner: Vec::new(), nested: vec![None, Some(Vec::new())] })
+        };
+        let binary = holds.to_binary_vec();
+        assert_eq!(binary.len(), holds.binary_size());
+        assert_eq!(HoldsDeeper::from_binary_slice(&binary), holds);
+    }
+    #[test]
+    fn groups_in_generics() {
+        let grouped = Grouped { source: 1_u8, bytes: vec![2_u8, 3] };
+        assert_eq!(grouped.source.source()(), 4);
+        let binary = grouped.to_binary_vec();
+        assert_eq!(binary, [1, 2, 0, 0, 0, 0, 0, 0, 0, 2, 3]);
+        assert_eq!(grouped.binary_size(), binary.len());
+        assert_eq!(Grouped::from_binary_slice(&binary), grouped);
+    }
+    #[test]
+    fn bounds_and_defaults() {
+        let mut binary = Vec::new();
+        S::<u8> { a: 1, b: -2 }.to_binary(&mut binary);
+        assert_eq!(binary, [1, 254, 255, 255, 255]);
+        assert_eq!(S::<u8>::from_binary(&mut binary.as_slice()), S { a: 1, b: -2 });
+    }
+    #[test]
+    fn comma_in_field_type() {
+        let mut binary = Vec::new();
+        Nested { inner: S { a: 1, b: 2 }, after: 3 }.to_binary(&mut binary);
+        assert_eq!(binary, [1, 2, 0, 3]);
+        assert_eq!(
+            Nested::from_binary(&mut binary.as_slice()),
+            Nested { inner: S { a: 1, b: 2 }, after: 3 }
+        );
+    }
+    // The impl header is built differently when there aren't any generics
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq)]
+    #[binary(checksum)]
+    struct Plain {
+        value: u16
+    }
+    fn implements<T: ToBinary + FromBinary + BinarySize + BinaryDiff>() {}
+
+    #[test]
+    fn no_generics() {
+        implements::<Plain>();
+        let plain = Plain { value: 3 };
+        let binary = plain.to_binary_vec();
+        assert_eq!(binary.len(), plain.binary_size());
+        assert_eq!(Plain::from_binary_slice(&binary), plain);
+        assert!(plain.binary_diff(&Plain { value: 3 }).is_empty());
+    }
+
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq)]
+    struct Where<T> where T: Copy {
+        a: T
+    }
+    // The () in the where clause isn't the fields
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct WhereGrouped<T, U>
+    where
+        T: Source<fn() -> u8>,
+        U: Copy + Into<u64>,
+    {
+        source: T,
+        value: U
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    enum WhereEnum<T> where T: Copy {
+        A(T),
+        B
+    }
+
+    #[test]
+    fn where_clause() {
+        implements::<Where<u16>>();
+        let value = Where { a: 5_u16 };
+        assert_eq!(Where::from_binary_slice(&value.to_binary_vec()), value);
+        let grouped = WhereGrouped { source: 1_u8, value: 2_u32 };
+        let binary = grouped.to_binary_vec();
+        assert_eq!(binary.len(), grouped.binary_size());
+        assert_eq!(WhereGrouped::from_binary_slice(&binary), grouped);
+        assert_eq!(grouped.source.source()(), 4);
+        for value in [WhereEnum::A(3_u8), WhereEnum::B] {
+            assert_eq!(WhereEnum::from_binary_slice(&value.to_binary_vec()), value);
+        }
+    }
+}
+
+mod framed {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(framed)]
+    struct Framed {
+        a: u32,
+        b: String
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(framed, tagged)]
+    struct FramedTagged {
+        a: u32
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut binary = Vec::new();
+        Framed { a: 1, b: "framed".to_string() }.to_binary(&mut binary);
+        assert_eq!(
+            Framed::from_binary(&mut binary.as_slice()),
+            Framed { a: 1, b: "framed".to_string() }
+        );
+    }
+    #[test]
+    fn skip() {
+        let mut binary = Vec::new();
+        Framed { a: 1, b: "framed".to_string() }.to_binary(&mut binary);
+        7_u8.to_binary(&mut binary);
+        let mut read = binary.as_slice();
+        // Skipping without knowing what it is
+        let len = u64::from_binary(&mut read) as usize;
+        assert_eq!(len, 4 + 8 + 6);
+        read = &read[len..];
+        assert_eq!(u8::from_binary(&mut read), 7);
+    }
+    #[test]
+    fn with_tagged() {
+        let mut binary = Vec::new();
+        FramedTagged { a: 1 }.to_binary(&mut binary);
+        // Frame length, field count, field length, field
+        assert_eq!(binary.len(), 8 + 4 + 8 + 4);
+        assert_eq!(FramedTagged::from_binary(&mut binary.as_slice()), FramedTagged { a: 1 });
+    }
+}
+
+mod default_on_eof {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(default_on_eof)]
+    struct Record {
+        id: u32,
+        name: String,
+        score: Option<u16>,
+        flags: Vec<u8>
+    }
+
+    #[test]
+    fn truncated() {
+        // Written by an older version that only had id and name
+        let mut binary = Vec::new();
+        7_u32.to_binary(&mut binary);
+        "old".to_string().to_binary(&mut binary);
+        assert_eq!(
+            Record::from_binary_slice(&binary),
+            Record { id: 7, name: "old".to_string(), score: None, flags: Vec::new() }
+        );
+        assert_eq!(
+            Record::from_binary_slice(&[]),
+            Record { id: 0, name: String::new(), score: None, flags: Vec::new() }
+        );
+    }
+    #[test]
+    fn complete() {
+        let record = Record { id: 1, name: "new".to_string(), score: Some(3), flags: vec![4, 5] };
+        let binary = Record { id: 1, name: "new".to_string(), score: Some(3), flags: vec![4, 5] }.to_binary_vec();
+        let mut read = binary.as_slice();
+        assert_eq!(Record::from_binary(&mut read), record);
+        assert!(read.is_empty());
+    }
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq, Default)]
+    struct Empty {}
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(default_on_eof)]
+    struct ZeroSized {
+        a: (),
+        b: u8,
+        c: [u16; 0],
+        d: u8,
+        e: Empty
+    }
+
+    #[test]
+    fn zero_sized() {
+        let value = ZeroSized { a: (), b: 1, c: [], d: 2, e: Empty {} };
+        // Nothing after it gets taken by the fields that don't read anything
+        let mut binary = value.to_binary_vec();
+        binary.push(3);
+        let mut read = binary.as_slice();
+        assert_eq!(ZeroSized::from_binary(&mut read), value);
+        assert_eq!(read, [3]);
+        assert_eq!(ZeroSized::from_binary_slice(&[1]), ZeroSized { a: (), b: 1, c: [], d: 0, e: Empty {} });
+    }
+}
+
+mod default_rest {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Old {
+        id: u32,
+        name: String
+    }
+    // The fields don't need to be Default, only the struct
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Level {
+        value: u8
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(default_rest)]
+    struct New {
+        id: u32,
+        name: String,
+        level: Level,
+        tags: Vec<String>
+    }
+    impl Default for New {
+        fn default() -> Self {
+            New { id: 0, name: "unnamed".to_string(), level: Level { value: 3 }, tags: vec!["new".to_string()] }
+        }
+    }
+
+    #[test]
+    fn shorter() {
+        let binary = Old { id: 7, name: "old".to_string() }.to_binary_vec();
+        assert_eq!(
+            New::from_binary_slice(&binary),
+            New { id: 7, name: "old".to_string(), level: Level { value: 3 }, tags: vec!["new".to_string()] }
+        );
+        assert_eq!(New::from_binary_slice(&7_u32.to_binary_vec()), New { id: 7, ..Default::default() });
+        assert_eq!(New::from_binary_slice(&[]), New::default());
+    }
+    #[test]
+    fn complete() {
+        let new = New { id: 1, name: "new".to_string(), level: Level { value: 9 }, tags: Vec::new() };
+        let binary = new.to_binary_vec();
+        let mut read = binary.as_slice();
+        assert_eq!(New::from_binary(&mut read), new);
+        assert!(read.is_empty());
+    }
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(default_rest)]
+    struct ZeroSized {
+        a: u8,
+        b: (),
+        c: u8,
+        d: [u8; 0]
+    }
+    impl Default for ZeroSized {
+        fn default() -> Self {
+            ZeroSized { a: 5, b: (), c: 6, d: [] }
+        }
+    }
+
+    #[test]
+    fn zero_sized() {
+        let value = ZeroSized { a: 1, b: (), c: 2, d: [] };
+        // Nothing after it gets taken by the fields that don't read anything
+        let mut binary = value.to_binary_vec();
+        binary.push(3);
+        let mut read = binary.as_slice();
+        assert_eq!(ZeroSized::from_binary(&mut read), value);
+        assert_eq!(read, [3]);
+        assert_eq!(ZeroSized::from_binary_slice(&[1]), ZeroSized { a: 1, ..Default::default() });
+    }
+}
+
+mod checksum {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(checksum)]
+    struct Checked {
+        a: u32,
+        b: String
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(checksum, framed)]
+    enum CheckedFramed {
+        A(u8),
+        B(u16)
+    }
+
+    #[test]
+    fn round_trip() {
+        let checked = Checked { a: 1, b: "checked".to_string() };
+        let binary = checked.to_binary_vec();
+        assert_eq!(binary.len(), checked.binary_size());
+        assert_eq!(binary[binary.len() - 4..], crc32(&binary[..binary.len() - 4]).to_le_bytes());
+        assert_eq!(Checked::from_binary_slice(&binary), checked);
+
+        let framed = CheckedFramed::B(2);
+        let binary = framed.to_binary_vec();
+        assert_eq!(binary.len(), framed.binary_size());
+        assert_eq!(CheckedFramed::from_binary_slice(&binary), framed);
+    }
+    #[test]
+    #[should_panic(expected = "checksum mismatch for Checked")]
+    fn corrupted() {
+        let mut binary = Checked { a: 1, b: "checked".to_string() }.to_binary_vec();
+        binary[13] ^= 1;
+        Checked::from_binary_slice(&binary);
+    }
+}
+
+mod compress {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Plain {
+        id: u32,
+        padding: Vec<u8>
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(compress)]
+    struct Compressed {
+        id: u32,
+        padding: Vec<u8>
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(compress, checksum, framed)]
+    enum CompressedFramed {
+        Empty,
+        Zeros(Vec<u64>)
+    }
+
+    #[test]
+    fn smaller() {
+        let plain = Plain { id: 7, padding: vec![0; 1000] };
+        let compressed = Compressed { id: 7, padding: vec![0; 1000] };
+        let binary = compressed.to_binary_vec();
+        assert!(binary.len() < plain.to_binary_vec().len() / 10, "{} bytes", binary.len());
+        assert_eq!(binary.len(), compressed.binary_size());
+        assert_eq!(Compressed::from_binary_slice(&binary), compressed);
+    }
+    #[test]
+    fn wrapped() {
+        for value in [CompressedFramed::Empty, CompressedFramed::Zeros(vec![0; 100])] {
+            let binary = value.to_binary_vec();
+            assert_eq!(binary.len(), value.binary_size());
+            assert_eq!(CompressedFramed::from_binary_slice(&binary), value);
+        }
+    }
+    #[test]
+    #[should_panic(expected = "ran out of data reading compressed Compressed")]
+    fn cut_off() {
+        let binary = Compressed { id: 7, padding: vec![1, 2, 3] }.to_binary_vec();
+        Compressed::from_binary_slice(&binary[..binary.len() - 2]);
+    }
+}
+
+mod align {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(align = 8)]
+    struct Aligned {
+        a: u8,
+        b: String
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, FixedSize, Debug, PartialEq)]
+    #[binary(align = 4, checksum)]
+    struct Small {
+        value: u8
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(align = 16, framed)]
+    enum AlignedFramed {
+        A,
+        B(u32)
+    }
+
+    #[test]
+    fn padded() {
+        for b in ["", "a", "exactly"] {
+            let aligned = Aligned { a: 1, b: b.to_string() };
+            let binary = aligned.to_binary_vec();
+            assert_eq!(binary.len() % 8, 0);
+            assert_eq!(binary.len(), aligned.binary_size());
+            assert!(binary[1 + 8 + b.len()..].iter().all(|byte| *byte == 0));
+            assert_eq!(Aligned::from_binary_slice(&binary), aligned);
+        }
+        // 7 bytes already means there is nothing to pad
+        assert_eq!(Aligned { a: 1, b: "exactly".to_string() }.to_binary_vec().len(), 16);
+        for value in [AlignedFramed::A, AlignedFramed::B(2)] {
+            let binary = value.to_binary_vec();
+            assert_eq!(binary.len(), 16);
+            assert_eq!(binary.len(), value.binary_size());
+            assert_eq!(AlignedFramed::from_binary_slice(&binary), value);
+        }
+    }
+    #[test]
+    fn skipped() {
+        // The padding of each one gets skipped so the next one starts in the right place
+        let values = vec![Small { value: 1 }, Small { value: 2 }, Small { value: 3 }];
+        let binary = values.to_binary_vec();
+        assert_eq!(binary.len(), 8 + 3 * 8);
+        assert_eq!(Small::SERIALIZED_SIZE, 8);
+        assert_eq!(Small { value: 1 }.binary_size(), 8);
+        assert_eq!(Vec::<Small>::from_binary_slice(&binary), values);
+    }
+}
+
+mod hygiene {
+    use super::*;
+
+    // The impls are in `const _` blocks, which this can sit next to,
+    // and nothing called the same as what they use gets in the way
+    const _: () = ();
+    #[allow(dead_code)]
+    fn from_binary() {}
+    #[allow(dead_code)]
+    fn to_binary() {}
+    #[allow(dead_code)]
+    fn binary_size() {}
+    #[allow(dead_code)]
+    struct Frame;
+    #[allow(dead_code)]
+    fn frame() {}
+    #[allow(dead_code)]
+    fn crc32() {}
+    #[allow(dead_code)]
+    struct VarInt;
+
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq)]
+    #[binary(framed, checksum)]
+    struct Neighbour {
+        value: u16
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(discriminant = "varint")]
+    enum NeighbourEnum {
+        A(Neighbour)
+    }
+
+    #[test]
+    fn next_to_user_items() {
+        let value = NeighbourEnum::A(Neighbour { value: 2 });
+        let binary = value.to_binary_vec();
+        assert_eq!(binary.len(), value.binary_size());
+        assert_eq!(NeighbourEnum::from_binary_slice(&binary), value);
+        assert!(Neighbour { value: 1 }.binary_diff(&Neighbour { value: 1 }).is_empty());
+    }
+
+    // The field type comes from the macro_rules!, so it has that hygiene instead of the derive's
+    macro_rules! make {
+        ($name:ident, $($t:tt)*) => {
+            #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq)]
+            struct $name {
+                a: $($t)*,
+                b: u8
+            }
+        };
+    }
+    make!(FromMacro, Vec<u16>);
+
+    #[test]
+    fn field_type_from_macro() {
+        let value = FromMacro { a: vec![1, 2], b: 3 };
+        let binary = value.to_binary_vec();
+        assert_eq!(binary.len(), value.binary_size());
+        assert_eq!(FromMacro::from_binary_slice(&binary), value);
+        assert!(value.binary_diff(&FromMacro { a: vec![1, 2], b: 3 }).is_empty());
+    }
+}
+
+mod derives_only {
+    // Nothing from abes_nice_binary is imported, so the derives have to name all of it
+    use abes_nice_procs::{ToBinary, FromBinary, BinarySize};
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(checksum, compress, framed)]
+    struct Alone {
+        a: u8,
+        #[binary(cstr)]
+        b: String
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(discriminant = "varint", align = 4)]
+    enum AloneEnum {
+        A(Alone)
+    }
+
+    #[test]
+    fn without_imports() {
+        use abes_nice_binary::{ToBinary, FromBinary, BinarySize};
+        let value = AloneEnum::A(Alone { a: 1, b: "two".to_string() });
+        let binary = value.to_binary_vec();
+        assert_eq!(binary.len(), value.binary_size());
+        assert_eq!(AloneEnum::from_binary_slice(&binary), value);
+    }
+}
+
+mod lints {
+    #![deny(warnings, clippy::all, clippy::pedantic)]
+    use super::*;
+
+    #[allow(non_snake_case)]
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(tagged, framed, checksum)]
+    pub struct Linted {
+        pub SHOUTING: u8,
+        pub r#type: Vec<u16>
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(discriminant = "varint")]
+    pub enum LintedEnum {
+        Empty,
+        Full(u8, Linted)
+    }
+
+    #[test]
+    fn no_warnings() {
+        let value = LintedEnum::Full(1, Linted { SHOUTING: 2, r#type: vec![3] });
+        assert_eq!(LintedEnum::from_binary_slice(&value.to_binary_vec()), value);
+        assert_eq!(LintedEnum::Empty.binary_size(), 1);
+    }
+}
+
+mod renamed {
+    // Only the derives are imported here, and the names of the traits
+    // are taken by something else, so they have to come from the path
+    use abes_nice_procs::{ToBinary, FromBinary, BinarySize};
+
+    pub mod runtime {
+        pub use abes_nice_binary::*;
+    }
+    #[allow(dead_code)]
+    trait ToBinary {}
+    #[allow(dead_code)]
+    struct FromBinary;
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(crate = "crate::renamed::runtime", checksum)]
+    struct Renamed<T> {
+        a: u8,
+        b: T
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(crate = "crate::renamed::runtime", discriminant = "varint")]
+    enum RenamedEnum {
+        A(Renamed<u16>)
+    }
+
+    #[test]
+    fn through_reexport() {
+        let value = RenamedEnum::A(Renamed { a: 1, b: 2 });
+        let binary = runtime::ToBinary::to_binary_vec(&value);
+        assert_eq!(binary.len(), runtime::BinarySize::binary_size(&value));
+        assert_eq!(<RenamedEnum as runtime::FromBinary>::from_binary_slice(&binary), value);
+    }
+}
+
+mod reuse {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq, Default)]
+    #[binary(reuse)]
+    struct Buffers {
+        id: u32,
+        name: String,
+        values: Vec<u32>,
+        names: Vec<String>
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq, Default)]
+    #[binary(reuse, framed, checksum)]
+    struct Wrapped {
+        inner: Buffers
+    }
+
+    fn buffers(size: usize) -> Buffers {
+        Buffers {
+            id: size as u32,
+            name: "n".repeat(size),
+            values: (0..size as u32).collect(),
+            names: vec!["name".repeat(size); 2]
+        }
+    }
+
+    #[test]
+    fn keeps_capacity() {
+        let mut read = Buffers::default();
+        read.from_binary_into(&mut buffers(100).to_binary_vec().as_slice());
+        assert_eq!(read, buffers(100));
+        let (name, values, names) = (read.name.as_ptr(), read.values.as_ptr(), read.names[0].as_ptr());
+        let capacity = read.values.capacity();
+
+        // Something smaller fits in what is already there
+        read.from_binary_into(&mut buffers(10).to_binary_vec().as_slice());
+        assert_eq!(read, buffers(10));
+        assert_eq!(read.name.as_ptr(), name);
+        assert_eq!(read.values.as_ptr(), values);
+        assert_eq!(read.names[0].as_ptr(), names);
+        assert_eq!(read.values.capacity(), capacity);
+    }
+    #[test]
+    fn with_wrappers() {
+        let mut read = Wrapped::default();
+        let mut binary = Wrapped { inner: buffers(5) }.to_binary_vec();
+        7_u8.to_binary(&mut binary);
+        let mut slice = binary.as_slice();
+        read.from_binary_into(&mut slice);
+        assert_eq!(read, Wrapped { inner: buffers(5) });
+        assert_eq!(slice, [7]);
+    }
+}
+
+mod diff {
+    use super::*;
+
+    // No PartialEq, only ToBinary is needed
+    #[derive(ToBinary, BinaryDiff)]
+    struct Settings<T> {
+        name: String,
+        volume: u8,
+        extra: T,
+        tags: Vec<String>
+    }
+    #[derive(ToBinary, BinaryDiff)]
+    enum Message {
+        Ping,
+        Text(u8, String)
+    }
+
+    fn settings() -> Settings<u16> {
+        Settings { name: "a".to_string(), volume: 5, extra: 1, tags: vec!["x".to_string()] }
+    }
+
+    #[test]
+    fn changed_fields() {
+        assert!(settings().binary_diff(&settings()).is_empty());
+        let changed = Settings { volume: 6, tags: Vec::new(), ..settings() };
+        assert_eq!(settings().binary_diff(&changed), ["volume", "tags"]);
+        let changed = Settings { extra: 2, ..settings() };
+        assert_eq!(changed.binary_diff(&settings()), ["extra"]);
+    }
+    #[test]
+    fn changed_variants() {
+        let text = Message::Text(1, "a".to_string());
+        assert!(text.binary_diff(&Message::Text(1, "a".to_string())).is_empty());
+        assert_eq!(text.binary_diff(&Message::Text(1, "b".to_string())), ["Text.1"]);
+        assert_eq!(text.binary_diff(&Message::Ping), ["variant"]);
+        assert!(Message::Ping.binary_diff(&Message::Ping).is_empty());
+    }
+}
+
+mod strict {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(strict)]
+    struct Strict {
+        a: u16,
+        b: u8
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Lenient {
+        a: u16,
+        b: u8
+    }
+
+    fn trailing() -> Vec<u8> {
+        let mut binary = Strict { a: 1, b: 2 }.to_binary_vec();
+        binary.extend_from_slice(&[3, 4]);
+        binary
+    }
+
+    #[test]
+    fn exact() {
+        let binary = Strict { a: 1, b: 2 }.to_binary_vec();
+        assert_eq!(Strict::from_binary_slice(&binary), Strict { a: 1, b: 2 });
+        let mut reader = BinaryReader::with_limit(binary.as_slice(), binary.len() as u64);
+        assert_eq!(reader.read_binary::<Strict>(), Strict { a: 1, b: 2 });
+        // Without a limit, the reader can't know what is left
+        let binary = trailing();
+        assert_eq!(BinaryReader::new(binary.as_slice()).read_binary::<Strict>(), Strict { a: 1, b: 2 });
+    }
+    #[test]
+    fn lenient() {
+        let binary = trailing();
+        assert_eq!(Lenient::from_binary_slice(&binary), Lenient { a: 1, b: 2 });
+        let mut reader = BinaryReader::with_limit(binary.as_slice(), binary.len() as u64);
+        assert_eq!(reader.read_binary::<Lenient>(), Lenient { a: 1, b: 2 });
+        assert_eq!(reader.remaining(), Some(2));
+    }
+    #[test]
+    #[should_panic(expected = "2 bytes were left over after reading")]
+    fn trailing_slice() {
+        Strict::from_binary_slice(&trailing());
+    }
+    #[test]
+    #[should_panic(expected = "2 bytes were left over after reading")]
+    fn trailing_reader() {
+        let binary = trailing();
+        BinaryReader::with_limit(binary.as_slice(), binary.len() as u64).read_binary::<Strict>();
+    }
+}
+
+mod narrowed {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq, Default)]
+    #[binary(reuse)]
+    struct Narrowed {
+        #[binary(as = u16)]
+        length: u64,
+        /// Doc comments can go with it
+        #[binary(as = i8)]
+        offset: i64,
+        plain: u8
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    enum Shape {
+        Line(#[binary(as = u8)] usize),
+        Point
+    }
+
+    #[test]
+    fn round_trip() {
+        let narrowed = Narrowed { length: 0x0201, offset: -3, plain: 4 };
+        let binary = narrowed.to_binary_vec();
+        assert_eq!(binary, [1, 2, 253, 4]);
+        assert_eq!(narrowed.binary_size(), 4);
+        assert_eq!(Narrowed::from_binary_slice(&binary), narrowed);
+        let mut read = Narrowed::default();
+        read.from_binary_into(&mut binary.as_slice());
+        assert_eq!(read, narrowed);
+        assert_eq!(narrowed.binary_diff(&Narrowed { length: 1, ..Default::default() }), ["length", "offset", "plain"]);
+
+        let line = Shape::Line(200);
+        let binary = line.to_binary_vec();
+        assert_eq!(binary, [0, 0, 0, 0, 200]);
+        assert_eq!(line.binary_size(), 5);
+        assert_eq!(Shape::from_binary_slice(&binary), line);
+    }
+    #[test]
+    #[should_panic(expected = "length doesn't fit in u16")]
+    fn too_big() {
+        Narrowed { length: 70000, offset: 0, plain: 0 }.to_binary_vec();
+    }
+}
+
+mod cstr {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq)]
+    struct Header {
+        #[binary(cstr)]
+        name: String,
+        version: u8
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(zero_copy)]
+    struct Borrowed<'a> {
+        #[binary(cstr)]
+        name: &'a str,
+        version: u8
+    }
+
+    #[test]
+    fn round_trip() {
+        let header = Header { name: "abc".to_string(), version: 2 };
+        let binary = header.to_binary_vec();
+        assert_eq!(binary, [b'a', b'b', b'c', 0, 2]);
+        assert_eq!(header.binary_size(), 5);
+        assert_eq!(Header::from_binary_slice(&binary), header);
+
+        let empty = Header { name: String::new(), version: 3 };
+        assert_eq!(Header::from_binary_slice(&empty.to_binary_vec()), empty);
+
+        let borrowed = Borrowed { name: "abc", version: 2 };
+        assert_eq!(borrowed.to_binary_vec(), binary);
+        assert_eq!(Borrowed::from_binary_borrowed(&mut binary.as_slice()), borrowed);
+    }
+    #[test]
+    #[should_panic(expected = "\"a\\0b\" has a nul in it")]
+    fn embedded_nul() {
+        Header { name: "a\0b".to_string(), version: 0 }.to_binary_vec();
+    }
+    #[test]
+    #[should_panic(expected = "ran out of data before the end of a NulTerminated")]
+    fn unterminated() {
+        Header::from_binary_slice(b"abc");
+    }
+}
+
+mod zero_copy {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(zero_copy)]
+    struct Packet<'a, T> {
+        id: u16,
+        payload: &'a [u8],
+        name: &'a str,
+        #[binary(as = u8)]
+        count: u32,
+        extra: T
+    }
+
+    #[test]
+    fn borrowed() {
+        let packet = Packet { id: 1, payload: &[1, 2, 3, 4], name: "packet", count: 2, extra: vec![5_u8] };
+        let binary = packet.to_binary_vec();
+        assert_eq!(binary.len(), packet.binary_size());
+        let mut rest = binary.as_slice();
+        let read = Packet::<Vec<u8>>::from_binary_borrowed(&mut rest);
+        assert_eq!(read, packet);
+        assert!(rest.is_empty());
+        // Pointing into binary means it wasn't copied
+        assert!(binary.as_ptr_range().contains(&read.payload.as_ptr()));
+        assert!(binary.as_ptr_range().contains(&read.name.as_ptr()));
+    }
+    #[test]
+    #[should_panic(expected = "ran out of data reading &[u8]")]
+    fn too_short() {
+        let binary = Packet { id: 1, payload: &[1, 2, 3, 4], name: "", count: 0, extra: () }.to_binary_vec();
+        Packet::<()>::from_binary_borrowed(&mut &binary[..12]);
+    }
+}
+
+mod size {
+    use super::*;
+
+    #[derive(ToBinary, BinarySize, Clone)]
+    struct Plain<T> {
+        a: u32,
+        b: String,
+        c: Option<Vec<T>>,
+        d: bool
+    }
+    #[derive(ToBinary, BinarySize, Clone)]
+    #[binary(framed, tagged)]
+    struct Wrapped {
+        a: u16,
+        b: Vec<u8>
+    }
+
+    fn written<T: ToBinary>(value: T) -> usize {
+        let mut binary = Vec::new();
+        value.to_binary(&mut binary);
+        binary.len()
+    }
+
+    #[test]
+    fn plain() {
+        let value = Plain { a: 1, b: "hello".to_string(), c: Some(vec![1_i64, 2, 3]), d: true };
+        assert_eq!(value.binary_size(), written(value.clone()));
+        let value = Plain::<i64> { a: 1, b: String::new(), c: None, d: false };
+        assert_eq!(value.binary_size(), written(value.clone()));
+    }
+    #[test]
+    fn wrapped() {
+        let value = Wrapped { a: 1, b: vec![1, 2, 3] };
+        assert_eq!(value.binary_size(), written(value.clone()));
+    }
+}
+
+mod fixed_size {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[derive(ToBinary, FromBinary, FixedSize, Debug, PartialEq)]
+    struct Header {
+        magic: u32,
+        version: u8,
+        flags: bool,
+        address: Ipv4Addr,
+        #[binary(as = u16)]
+        length: u64,
+        _marker: std::marker::PhantomData<String>
+    }
+    #[derive(ToBinary, FixedSize)]
+    #[binary(framed, checksum, tagged)]
+    struct Wrapped<T> {
+        inner: T,
+        time: std::time::Duration
+    }
+    #[derive(ToBinary, FixedSize)]
+    #[binary(discriminant = "varint")]
+    enum Same {
+        A(u32),
+        B(u16, u16),
+        C(char)
+    }
+
+    #[test]
+    fn header() {
+        let header = Header {
+            magic: 0xabe5,
+            version: 1,
+            flags: true,
+            address: Ipv4Addr::LOCALHOST,
+            length: 12,
+            _marker: std::marker::PhantomData
+        };
+        // Enough for a buffer on the stack
+        let mut buffer = [0_u8; Header::SERIALIZED_SIZE];
+        header.to_binary(&mut buffer.as_mut_slice());
+        assert_eq!(Header::SERIALIZED_SIZE, 12);
+        assert_eq!(buffer.as_slice(), header.to_binary_vec());
+        assert_eq!(Header::from_binary_slice(&buffer), header);
+    }
+    #[test]
+    fn wrapped() {
+        let wrapped = Wrapped { inner: 1_i64, time: std::time::Duration::from_secs(5) };
+        assert_eq!(Wrapped::<i64>::SERIALIZED_SIZE, wrapped.to_binary_vec().len());
+        for same in [Same::A(1), Same::B(2, 3), Same::C('c')] {
+            assert_eq!(Same::SERIALIZED_SIZE, same.to_binary_vec().len());
+        }
+        assert_eq!(Same::SERIALIZED_SIZE, 5);
+    }
+}
+
+mod enums {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq, Clone)]
+    enum Shape {
+        Empty,
+        /// Documented
+        Circle(u32),
+        Rectangle(u16, u16)
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq, Clone)]
+    #[binary(discriminant = "varint")]
+    enum Small {
+        A,
+        B(u8)
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq, Clone)]
+    #[binary(tag = "name")]
+    enum Named {
+        Off,
+        On(u8),
+        r#Type
+    }
+    // Named with the variants moved around, which doesn't matter when it is by name
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(tag = "name")]
+    enum NamedMoved {
+        r#Type,
+        On(u8),
+        Off
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq, Clone)]
+    enum Geometry<T> {
+        Point { x: u32, y: u32 },
+        Line(u8, u8),
+        Labeled {
+            /// Documented
+            label: String,
+            #[binary(as = u8)]
+            r#type: u32,
+            value: T,
+        },
+        Empty {}
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, BinaryDiff, Debug, PartialEq, Clone)]
+    #[non_exhaustive]
+    pub enum Open {
+        A,
+        #[non_exhaustive]
+        B(u8),
+        #[non_exhaustive]
+        C { value: u16 }
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[non_exhaustive]
+    pub struct OpenStruct {
+        pub value: Open
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq, Clone)]
+    enum Either<L, R> {
+        Left(L),
+        Right(R)
+    }
+
+    // Two versions of the same enum, where the old one is missing a variant
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(framed, unknown = Unknown)]
+    enum Old {
+        A(u8),
+        Unknown
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(framed, unknown = Unknown)]
+    enum New {
+        A(u8),
+        Unknown,
+        B(String)
+    }
+
+    fn written<T: ToBinary>(value: T) -> Vec<u8> {
+        let mut binary = Vec::new();
+        value.to_binary(&mut binary);
+        binary
+    }
+
+    #[test]
+    fn round_trip() {
+        for shape in [Shape::Empty, Shape::Circle(5), Shape::Rectangle(1, 2)] {
+            let binary = written(shape.clone());
+            assert_eq!(binary.len(), shape.binary_size());
+            assert_eq!(Shape::from_binary(&mut binary.as_slice()), shape);
+        }
+        assert_eq!(written(Shape::Rectangle(1, 2)), [2, 0, 0, 0, 1, 0, 2, 0]);
+    }
+    #[test]
+    fn generic() {
+        let value = Either::<u8, String>::Right("hi".into());
+        let binary = written(value.clone());
+        assert_eq!(binary, [1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i']);
+        assert_eq!(binary.len(), value.binary_size());
+        assert_eq!(Either::<u8, String>::from_binary(&mut binary.as_slice()), value);
+        let left = Either::<u8, String>::Left(3);
+        assert_eq!(Either::from_binary(&mut written(left.clone()).as_slice()), left);
+    }
+    #[test]
+    fn unknown_variant() {
+        let mut binary = Vec::new();
+        New::B("new".to_string()).to_binary(&mut binary);
+        New::A(4).to_binary(&mut binary);
+        let mut read = binary.as_slice();
+        assert_eq!(Old::from_binary(&mut read), Old::Unknown);
+        // Which still skipped all of B
+        assert_eq!(Old::from_binary(&mut read), Old::A(4));
+        assert!(read.is_empty());
+        assert_eq!(New::from_binary_slice(&Old::A(5).to_binary_vec()), New::A(5));
+    }
+    #[test]
+    fn varint_discriminant() {
+        assert_eq!(written(Small::A), [0]);
+        assert_eq!(written(Small::B(7)), [1, 7]);
+        assert_eq!(Small::B(7).binary_size(), 2);
+        assert_eq!(Small::from_binary(&mut [1, 7].as_slice()), Small::B(7));
+    }
+    #[test]
+    fn struct_variants() {
+        let values = [
+            Geometry::Point { x: 1, y: 2 },
+            Geometry::Line(3, 4),
+            Geometry::Labeled { label: "label".to_string(), r#type: 5, value: -1_i8 },
+            Geometry::Empty {}
+        ];
+        for value in values {
+            let binary = written(value.clone());
+            assert_eq!(binary.len(), value.binary_size());
+            assert_eq!(Geometry::from_binary_slice(&binary), value);
+        }
+        assert_eq!(written(Geometry::<()>::Point { x: 1, y: 2 }), [0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(
+            Geometry::<()>::Point { x: 1, y: 2 }.binary_diff(&Geometry::Point { x: 1, y: 3 }),
+            ["Point.y"]
+        );
+    }
+    #[test]
+    fn non_exhaustive() {
+        for value in [Open::A, Open::B(1), Open::C { value: 2 }] {
+            let binary = written(value.clone());
+            assert_eq!(binary.len(), value.binary_size());
+            let read = OpenStruct::from_binary_slice(&written(OpenStruct { value: value.clone() }));
+            assert_eq!(read, OpenStruct { value });
+        }
+    }
+    #[test]
+    fn name_tag() {
+        assert_eq!(written(Named::On(5)), [2, 0, 0, 0, 0, 0, 0, 0, b'O', b'n', 5]);
+        assert_eq!(written(Named::r#Type), [4, 0, 0, 0, 0, 0, 0, 0, b'T', b'y', b'p', b'e']);
+        for named in [Named::Off, Named::On(1), Named::r#Type] {
+            let binary = written(named.clone());
+            assert_eq!(binary.len(), named.binary_size());
+            assert_eq!(Named::from_binary_slice(&binary), named);
+        }
+        assert_eq!(NamedMoved::from_binary_slice(&written(Named::Off)), NamedMoved::Off);
+        assert_eq!(NamedMoved::from_binary_slice(&written(Named::On(2))), NamedMoved::On(2));
+    }
+    #[test]
+    #[should_panic(expected = "unknown variant name for Named: \"Dimmed\"")]
+    fn unknown_name() {
+        Named::from_binary_slice(&"Dimmed".to_binary_vec());
+    }
+    #[test]
+    #[should_panic(expected = "invalid discriminant for Shape: 3")]
+    fn invalid_discriminant() {
+        Shape::from_binary(&mut [3, 0, 0, 0].as_slice());
+    }
+}
+
+mod recursive {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Node {
+        value: u32,
+        next: Option<Box<Node>>
+    }
+
+    #[test]
+    fn linked_list() {
+        let list = Node {
+            value: 1,
+            next: Some(Box::new(Node {
+                value: 2,
+                next: Some(Box::new(Node { value: 3, next: None }))
+            }))
+        };
+        let mut binary = Vec::new();
+        list.to_binary(&mut binary);
+        assert_eq!(binary.len(), 3 * (4 + 1));
+        let list = Node::from_binary(&mut binary.as_slice());
+        let values = std::iter::successors(Some(&list), |node| node.next.as_deref())
+            .map(|node| node.value)
+            .collect::<Vec<u32>>();
+        assert_eq!(values, [1, 2, 3]);
+    }
+}
+
+mod associated {
+    use super::*;
+
+    trait Storage {
+        type Stored;
+    }
+    impl Storage for u8 {
+        type Stored = u8;
+    }
+    impl Storage for u16 {
+        type Stored = Vec<u16>;
+    }
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    struct Stores<T: Storage> {
+        stored: T::Stored,
+        plain: <u16 as Storage>::Stored,
+        boxed: Option<Box<Self>>
+    }
+    // The std derives can't bound this one, so it only gets ours
+    #[derive(ToBinary, FromBinary, BinarySize)]
+    struct Qualified<T: Storage> {
+        first: <T as Storage>::Stored,
+        nested: Vec<<T as Storage>::Stored>
+    }
+
+    #[test]
+    fn associated_types() {
+        let stores = Stores::<u8> {
+            stored: 5,
+            plain: vec![1],
+            boxed: Some(Box::new(Stores { stored: 6, plain: Vec::new(), boxed: None }))
+        };
+        let binary = stores.to_binary_vec();
+        assert_eq!(binary.len(), stores.binary_size());
+        assert_eq!(Stores::<u8>::from_binary_slice(&binary), stores);
+        let qualified = Qualified::<u16> { first: vec![1], nested: vec![vec![2, 3]] };
+        let binary = qualified.to_binary_vec();
+        assert_eq!(binary.len(), qualified.binary_size());
+        let read = Qualified::<u16>::from_binary_slice(&binary);
+        assert_eq!((read.first, read.nested), (qualified.first, qualified.nested));
+    }
+}
+
+mod convenience {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq, Clone)]
+    struct Point {
+        x: i32,
+        y: i32
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq, Clone)]
+    enum Either {
+        Left(Point),
+        Right(String)
+    }
+
+    #[test]
+    fn vec_and_slice() {
+        let point = Point { x: 1, y: -1 };
+        let binary = point.to_binary_vec();
+        assert_eq!(binary, [1, 0, 0, 0, 255, 255, 255, 255]);
+        assert_eq!(Point::from_binary_slice(&binary), point);
+
+        let either = Either::Right("right".to_string());
+        assert_eq!(Either::from_binary_slice(&either.to_binary_vec()), either);
+    }
+
+    // None of these are Clone, so writing them can't be taking them
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    struct Owned {
+        name: String,
+        items: Vec<Option<Box<u8>>>
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(tagged, framed)]
+    struct OwnedTagged {
+        name: String
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    enum OwnedEnum {
+        Named(String)
+    }
+
+    #[test]
+    fn write_twice() {
+        let owned = Owned { name: "owned".to_string(), items: vec![Some(Box::new(1)), None] };
+        let first = owned.to_binary_vec();
+        let mut second = Vec::new();
+        owned.to_binary(&mut second);
+        assert_eq!(first, second);
+        assert_eq!(Owned::from_binary_slice(&second), owned);
+
+        let tagged = OwnedTagged { name: "tagged".to_string() };
+        assert_eq!(tagged.to_binary_vec(), tagged.to_binary_vec());
+        assert_eq!(OwnedTagged::from_binary_slice(&tagged.to_binary_vec()), tagged);
+
+        let named = OwnedEnum::Named("named".to_string());
+        assert_eq!(named.to_binary_vec(), named.to_binary_vec());
+        assert_eq!(OwnedEnum::from_binary_slice(&named.to_binary_vec()), named);
+    }
+}
+
+mod layout {
+    use super::*;
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq, Clone)]
+    struct Record {
+        a: u8,
+        b: u16,
+        c: bool,
+        d: String,
+        e: i32
+    }
+
+    #[test]
+    fn field_order() {
+        let record = Record { a: 1, b: 0x0302, c: true, d: "hi".to_string(), e: -2 };
+        let binary = record.clone().to_binary_vec();
+        assert_eq!(
+            binary,
+            [
+                1,
+                2, 3,
+                1,
+                2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i',
+                254, 255, 255, 255
+            ]
+        );
+        assert_eq!(Record::from_binary_slice(&binary), record);
+    }
+
+    #[test]
+    fn both_endians() {
+        let record = Record { a: 1, b: 0x0302, c: true, d: "hi".to_string(), e: -2 };
+        let mut writer = BinaryWriter::big_endian(Vec::new());
+        writer.write_binary(&record);
+        let binary = writer.into_inner();
+        assert_eq!(
+            binary,
+            [
+                1,
+                3, 2,
+                1,
+                0, 0, 0, 0, 0, 0, 0, 2, b'h', b'i',
+                255, 255, 255, 254
+            ]
+        );
+        let read = BinaryReader::big_endian(binary.as_slice()).read_binary::<Record>();
+        assert_eq!(read, record);
+        assert_eq!(Record::from_binary_slice(&record.to_binary_vec()), read);
+    }
+
+    #[derive(ToBinary, FromBinary, BinarySize, Debug, PartialEq)]
+    #[binary(order(c, a, b))]
+    struct Reordered {
+        a: u8,
+        b: u16,
+        c: u32
+    }
+
+    #[test]
+    fn custom_order() {
+        let reordered = Reordered { a: 1, b: 0x0302, c: 0x07060504 };
+        let binary = reordered.to_binary_vec();
+        assert_eq!(binary, [4, 5, 6, 7, 1, 2, 3]);
+        assert_eq!(reordered.binary_size(), 7);
+        assert_eq!(Reordered::from_binary_slice(&binary), reordered);
+    }
+    // Both are the same type, so reading them into the wrong one wouldn't be caught by the types
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(order(second, first))]
+    struct Swapped {
+        first: u8,
+        second: u8
+    }
+
+    #[test]
+    fn reads_by_name() {
+        // Written in the documented order, which is second then first
+        assert_eq!(Swapped::from_binary_slice(&[1, 2]), Swapped { first: 2, second: 1 });
+        assert_eq!(Swapped { first: 2, second: 1 }.to_binary_vec(), [1, 2]);
+    }
+
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f32),
+        Rect { width: u16, height: u16 }
+    }
+    #[derive(ToBinary, FromBinary, Debug, PartialEq)]
+    #[binary(framed, checksum)]
+    struct Sample {
+        id: u64,
+        offset: i16,
+        scale: f64,
+        name: String,
+        initial: char,
+        tags: Vec<String>,
+        parent: Option<u32>,
+        shapes: Vec<Shape>,
+        corners: [[u8; 2]; 2],
+        counts: std::collections::BTreeMap<String, usize>,
+        enabled: bool
+    }
+    fn sample() -> Sample {
+        Sample {
+            id: 0x0102_0304_0506_0708,
+            offset: -300,
+            scale: 0.5,
+            name: "sample".to_string(),
+            initial: 'é',
+            tags: vec!["a".to_string(), "bc".to_string()],
+            parent: Some(9),
+            shapes: vec![Shape::Point, Shape::Circle(1.5), Shape::Rect { width: 3, height: 4 }],
+            corners: [[0, 0], [10, 20]],
+            counts: [("x".to_string(), 1), ("y".to_string(), 2)].into(),
+            enabled: true
+        }
+    }
+    // The bytes in the fixture were written by an earlier version,
+    // so if this fails, data that is already out there can't be read anymore
+    const FIXTURE: &[u8] = include_bytes!("fixtures/sample.bin");
+
+    #[test]
+    fn matches_fixture() {
+        assert!(sample().to_binary_vec() == FIXTURE, "wrote {:?}", sample().to_binary_vec());
+    }
+    #[test]
+    fn reads_fixture() {
+        assert_eq!(Sample::from_binary_slice(FIXTURE), sample());
+    }
+}
+
+#[test]
+fn method_str_encoded() {
+    let hex = method_str!(all_bytes_hex, encode = "hex",
+        use std::io::Write;
+        std::io::stdout().write_all(&(0..=255).collect::<Vec<u8>>()).unwrap();
+    );
+    let base64 = method_str!(all_bytes_base64, encode = "base64",
+        use std::io::Write;
+        std::io::stdout().write_all(&(0..=255).collect::<Vec<u8>>()).unwrap();
+    );
+    let from_hex = (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+        .collect::<Vec<u8>>();
+    assert_eq!(from_hex, (0..=255).collect::<Vec<u8>>());
+    // Undoing the base64 by hand, 4 characters at a time
+    let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut from_base64 = Vec::new();
+    for chunk in base64.as_bytes().chunks(4) {
+        let padding = chunk.iter().filter(|c| **c == b'=').count();
+        let bits = chunk.iter().fold(0_u32, |bits, c| {
+            bits << 6 | alphabet.find(*c as char).unwrap_or(0) as u32
+        });
+        from_base64.extend_from_slice(&bits.to_be_bytes()[1..4 - padding]);
+    }
+    assert_eq!(from_base64, (0..=255).collect::<Vec<u8>>());
+}
+#[test]
+fn method_cached() {
+    // Only running once means both get the same time
+    let first = method_str!(cached_first, cache_key = "now", print!("{:?}", std::time::SystemTime::now()));
+    let second = method_str!(cached_second, cache_key = "now", print!("{:?}", std::time::SystemTime::now()));
+    assert_eq!(first, second);
+}
+#[test]
+fn method_params() {
+    let small = method!(params_small, { PARAM = 5 }, fn main() { print!("{}", PARAM * 2) });
+    let large = method!(params_large, { PARAM = 50 }, fn main() { print!("{}", PARAM * 2) });
+    assert_eq!((small, large), (10, 100));
+    let typed = method!(params_typed, { SIZES: [u8; 3] = [1, 2, 3], NAME = "sizes", },
+        print!("({:?}, {})", NAME, SIZES.iter().sum::<u8>())
+    );
+    assert_eq!(typed, ("sizes", 6));
+    // A block followed by the code isn't params, it is the code
+    assert_eq!(method!(params_block, { let seven = 7; print!("{seven}") }), 7);
+}
+#[test]
+fn method_println() {
+    assert_eq!(method!(printed, print!("5")), method!(printed_line, println!("5")));
+    assert_eq!(method_str!(printed_str, println!("five")), "five");
+    assert_eq!(method_str!(printed_kept, trim = false, println!("five")), "five\n");
+}
+// Starting with something method! wouldn't guess is an item,
+// which matters when there are files to track
+method_items!(generated_items, deps = ["tests/generators/seven.rs"],
+    print!("std::thread_local!(static GENERATED_LOCAL: u8 = 3);");
+    print!("fn generated() -> u8 {{ 7 }}");
+);
+#[test]
+fn method_items() {
+    assert_eq!(generated(), 7);
+    assert_eq!(GENERATED_LOCAL.with(|local| *local), 3);
+}
+#[test]
+fn method_stderr() {
+    let value = method!(logged_stderr,
+        fn main() {
+            eprintln!("starting");
+            print!("[1, ");
+            eprintln!("halfway");
+            print!("2]");
+        }
+    );
+    assert_eq!(value, [1, 2]);
+}
+static_run_assert!(asserted_items,
+    fn main() {
+        assert!(std::path::Path::new("Cargo.toml").exists());
+    }
+);
+#[test]
+fn static_run_assert() {
+    static_run_assert!(asserted_statement, assert_eq!(1 + 1, 2));
+    static_run_assert!(asserted_deps, deps = ["Cargo.toml"],
+        assert!(std::fs::read_to_string("Cargo.toml").unwrap().contains("abes_nice_procs"))
+    );
+}
+#[test]
+fn method_status() {
+    let code: i32 = method_status!(status_three,
+        fn main() {
+            print!("not used");
+            std::process::exit(3);
+        }
+    );
+    assert_eq!(code, 3);
+    // Panicking is 101, which isn't an error here either
+    assert_eq!(method_status!(status_panic, panic!("expected")), 101);
+    assert_eq!(method_status!(status_success, fn main() {}), 0);
+}
+#[test]
+fn methods() {
+    let (a, b, c) = methods!(
+        (methods_a, print!("1")),
+        (methods_b, include = "tests/generators/seven.rs"),
+        (methods_c, fn main() { print!("[{}, {}]", 2, 3) })
+    );
+    assert_eq!(a, 1);
+    assert_eq!(b, 7);
+    assert_eq!(c, [2, 3]);
+}
+#[test]
+fn method_str() {
+    let text: &'static str = method_str!(str_output,
+        fn main() {
+            print!("quotes: \" ' backslash: \\ newline:\n tab:\t unicode: é {{}}");
+        }
+    );
+    assert_eq!(text, "quotes: \" ' backslash: \\ newline:\n tab:\t unicode: é {}");
+}
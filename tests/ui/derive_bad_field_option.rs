@@ -0,0 +1,9 @@
+use abes_nice_procs::ToBinary;
+
+#[derive(ToBinary)]
+struct Narrowed {
+    #[binary(narrow)]
+    length: u64
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use abes_nice_procs::ToBinary;
+
+#[derive(ToBinary)]
+#[binary(order(b, c))]
+struct Reordered {
+    a: u8,
+    b: u8
+}
+
+fn main() {}
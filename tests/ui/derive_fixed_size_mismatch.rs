@@ -0,0 +1,11 @@
+use abes_nice_procs::FixedSize;
+use abes_nice_binary::FixedSize;
+
+#[derive(FixedSize)]
+enum Different {
+    Small(u8),
+    Big(u64)
+}
+const _: usize = Different::SERIALIZED_SIZE;
+
+fn main() {}
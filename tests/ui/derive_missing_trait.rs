@@ -0,0 +1,11 @@
+use abes_nice_procs::{ToBinary, FromBinary};
+
+struct NotBinary;
+
+#[derive(ToBinary, FromBinary)]
+struct Holder {
+    fine: u8,
+    broken: NotBinary
+}
+
+fn main() {}
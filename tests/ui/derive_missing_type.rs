@@ -0,0 +1,9 @@
+use abes_nice_procs::FromBinary;
+
+#[derive(FromBinary)]
+struct Missing {
+    a: u8,
+    b
+}
+
+fn main() {}
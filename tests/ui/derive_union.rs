@@ -0,0 +1,9 @@
+use abes_nice_procs::ToBinary;
+
+#[derive(ToBinary)]
+union Either {
+    a: u32,
+    b: f32
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use abes_nice_procs::FromBinary;
+
+#[derive(FromBinary)]
+struct Unit;
+
+fn main() {}
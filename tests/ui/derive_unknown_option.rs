@@ -0,0 +1,9 @@
+use abes_nice_procs::FromBinary;
+
+#[derive(FromBinary)]
+#[binary(compressed)]
+struct Options {
+    value: u8
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use abes_nice_procs::FromBinary;
+
+#[derive(FromBinary)]
+#[binary(zero_copy)]
+struct Owned {
+    bytes: Vec<u8>
+}
+
+fn main() {}
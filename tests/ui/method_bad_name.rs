@@ -0,0 +1,5 @@
+use abes_nice_procs::method;
+
+fn main() {
+    let _ = method!("../escaped", fn main() {});
+}
@@ -0,0 +1,6 @@
+use abes_nice_procs::method;
+
+fn main() {
+    let _ = method!(bad_option_ui, fmt = maybe, print!("5"));
+    let _ = method!(unknown_option_ui, colour = "red", print!("5"));
+}
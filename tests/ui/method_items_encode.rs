@@ -0,0 +1,6 @@
+use abes_nice_procs::{method_items, static_run_assert};
+
+method_items!(encoded_items_ui, encode = "hex", print!("fn a() {{}}"));
+static_run_assert!(encoded_assert_ui, encode = "hex", fn main() {});
+
+fn main() {}
@@ -0,0 +1,8 @@
+use abes_nice_procs::method;
+
+fn main() {
+    let _ = method!(not_utf8_ui,
+        use std::io::Write;
+        std::io::stdout().write_all(b"let x = \xff;").unwrap();
+    );
+}
@@ -0,0 +1,10 @@
+use abes_nice_procs::static_run_assert;
+
+static_run_assert!(assert_fails_ui,
+    fn main() {
+        eprintln!("the check failed");
+        std::process::exit(1);
+    }
+);
+
+fn main() {}